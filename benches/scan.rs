@@ -0,0 +1,117 @@
+//! Times a dry run over a generated repository, searching the files one
+//! after another (`--jobs 1`) and then on every CPU.
+//!
+//! Run it with `cargo bench`. The number of files and runs can be set with
+//! the BENCH_FILES and BENCH_RUNS environment variables. To compare with
+//! the sequential search that read files line by line before the thread
+//! pool, set BENCH_BASELINE to a binary built from an earlier commit.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+const DEFAULT_FILES: usize = 2000;
+const DEFAULT_RUNS: usize = 5;
+const LINES_PER_FILE: usize = 400;
+
+fn main() {
+    let num_files = get_setting("BENCH_FILES", DEFAULT_FILES);
+    let num_runs = get_setting("BENCH_RUNS", DEFAULT_RUNS);
+    let repo = env::temp_dir()
+        .join(format!("todo-to-issue-bench-{}", std::process::id()));
+    create_repo(&repo, num_files);
+
+    println!(
+        "Searching {} files of {} lines, median of {} runs",
+        num_files, LINES_PER_FILE, num_runs
+    );
+    let binary = PathBuf::from(env!("CARGO_BIN_EXE_todo-to-issue"));
+    let baseline = env::var_os("BENCH_BASELINE").map(|path| {
+        let path = fs::canonicalize(path).expect("No baseline binary found");
+        let time = time_runs(&repo, &path, &[], num_runs);
+        println!("baseline: {:>8.1?}", time);
+        time
+    });
+    let sequential = time_runs(&repo, &binary, &["--jobs", "1"], num_runs);
+    println!("--jobs 1: {:>8.1?}", sequential);
+    let parallel = time_runs(&repo, &binary, &[], num_runs);
+    println!("default:  {:>8.1?}", parallel);
+    println!(
+        "speedup:  {:>8.2}x",
+        baseline.unwrap_or(sequential).as_secs_f64() / parallel.as_secs_f64()
+    );
+
+    fs::remove_dir_all(&repo).expect("Failed to remove the repository");
+}
+
+fn get_setting(name: &str, default: usize) -> usize {
+    env::var(name)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(default)
+}
+
+fn create_repo(repo: &Path, num_files: usize) {
+    //! Creates a git repository with files that mostly contain code, and a
+    //! todo every 50 lines.
+    fs::create_dir_all(repo.join("src")).expect("Failed to create the repo");
+    git(repo, &["init", "-q"]);
+    for file in 0..num_files {
+        let mut contents = String::new();
+        for line in 0..LINES_PER_FILE {
+            match line % 50 {
+                0 => {
+                    contents += &format!("// TODO: Fix {} of {}\n", line, file)
+                }
+                1 => contents += "// with some more text in its description\n",
+                _ => {
+                    contents +=
+                        &format!("let value_{} = \"// {}\";\n", line, file)
+                }
+            }
+        }
+        let path = repo.join("src").join(format!("file_{}.rs", file));
+        fs::write(path, contents).expect("Failed to write a file");
+    }
+    git(repo, &["add", "."]);
+}
+
+fn time_runs(
+    repo: &Path,
+    binary: &Path,
+    args: &[&str],
+    num_runs: usize,
+) -> Duration {
+    //! Returns the median time of the runs of the binary, each starting
+    //! without the todos cached by the previous one.
+    let mut times: Vec<Duration> = (0..num_runs)
+        .map(|_| {
+            let state = repo.join(".git").join("todo-to-issue");
+            let _ = fs::remove_dir_all(state);
+            let mut command = Command::new(binary);
+            command.arg("--dry-run").args(args).current_dir(repo);
+            let start = Instant::now();
+            let status = command
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status()
+                .expect("Failed to run todo-to-issue");
+            assert!(status.success(), "todo-to-issue failed: {}", status);
+            start.elapsed()
+        })
+        .collect();
+    times.sort();
+
+    times[times.len() / 2]
+}
+
+fn git(repo: &Path, args: &[&str]) {
+    let status = Command::new("git")
+        .args(args)
+        .current_dir(repo)
+        .status()
+        .expect("Failed to execute git");
+    assert!(status.success(), "git {} failed", args.join(" "));
+}
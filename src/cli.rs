@@ -1,27 +1,127 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 
-use clap::{App, Arg};
+use clap::{App, Arg, ArgMatches};
 use console::style;
-use dialoguer::{theme::ColorfulTheme, Editor, Select};
-use glob::Pattern;
+use dialoguer::{theme::ColorfulTheme, Confirmation, Editor, Select};
+use parse::Todo;
+use reqwest::Url;
+use serde_json::{json, Value};
 
 use super::command;
+use super::config;
+use super::fingerprint;
 use super::issue;
-use super::request;
+use super::marker;
+use super::parse;
+use super::pattern;
+use super::remote;
+use super::report;
+use super::state;
+use super::syntax;
+use super::template;
+use super::tracker;
 
-use issue::Issue;
-use request::Request;
+use command::{AddedLines, Source};
+use config::Config;
+use issue::{Issue, Opened};
+use marker::Marker;
+use pattern::Pattern;
+use remote::Remote;
+use report::Format;
+use state::State;
+use syntax::{Registry, Syntax};
+use template::{Template, Templates};
+use tracker::{Kind, Tracker};
 
-const SELECTIONS: &[&str] = &["Open Issue", "Edit Issue", "Skip Issue", "Exit"];
-const ALL_FILES: &str = "*";
+const SELECTIONS: &[&str] = &[
+    "Open Issue",
+    "Edit Issue",
+    "Skip Issue",
+    "Ignore Issue",
+    "Exit",
+];
+const CLOSED_SELECTIONS: &[&str] =
+    &["Reopen Issue", "Comment on Issue", "Skip Issue", "Exit"];
+
+/// Exit code when there was nothing new to open (or for interactive runs).
+pub const EXIT_SUCCESS: i32 = 0;
+/// Exit code when issues were opened in non-interactive mode, which is
+/// distinct from the codes of errors such as invalid arguments (EXIT_ERROR)
+/// and panics (101).
+pub const EXIT_OPENED: i32 = 10;
+/// Exit code when something went wrong.
+pub const EXIT_ERROR: i32 = 2;
+
+/// Whether output other than errors should be suppressed, e.g. so
+/// machine-readable output is not mixed with progress messages.
+static IS_QUIET: AtomicBool = AtomicBool::new(false);
 
 const OPEN: usize = 0;
 const EDIT: usize = 1;
 const SKIP: usize = 2;
+const IGNORE: usize = 3;
+
+const REOPEN: usize = 0;
+const COMMENT: usize = 1;
+
+/// What was done to existing issues before opening new ones, which is
+/// part of the non-interactive summary.
+#[derive(Default)]
+pub struct Summary {
+    closed: Vec<Value>,
+    reopened: Vec<Value>,
+    commented: Vec<Value>,
+    /// Closed issues whose todo is still in the code and were left as is.
+    still_present: Vec<Value>,
+    failed: Vec<Value>,
+    /// Number of todos that are never turned into issues.
+    ignored: usize,
+}
+
+impl Summary {
+    pub fn new(ignored: usize) -> Summary {
+        Summary {
+            ignored,
+            ..Summary::default()
+        }
+    }
+}
 
 pub struct Args {
-    pattern: Pattern,
+    /// Patterns of the files to search, or empty to search every file.
+    include: Vec<Pattern>,
+    exclude: Vec<Pattern>,
     token: String,
+    syntax: Registry,
+    markers: Vec<Marker>,
+    /// Identifies the settings that change which todos are found in a file.
+    scan_key: String,
+    labels: Vec<String>,
+    assignees: Vec<String>,
+    /// Maps lowercase commit author emails to users to assign.
+    authors: HashMap<String, String>,
+    templates: Templates,
+    /// Templates of specific markers, by keyword.
+    marker_templates: HashMap<String, Templates>,
+    /// The remote repository, which is None if it could not be found
+    /// and it is not needed.
+    remote: Option<Remote>,
+    tracker: Kind,
+    api_url: Option<String>,
+    source: Source,
+    /// The base revision and the lines added since, by file, if only
+    /// new todos should be searched for.
+    since: Option<(String, AddedLines)>,
+    format: Option<Format>,
+    /// Number of threads to search files with, or 0 for one per CPU.
+    jobs: usize,
+    is_ignore_case: bool,
+    is_write_back: bool,
+    is_non_interactive: bool,
+    is_sync: bool,
     is_dry_run: bool,
     is_verbose: bool,
 }
@@ -31,14 +131,145 @@ impl Args {
         self.token.clone()
     }
 
-    pub fn get_pattern(&self) -> &Pattern {
-        &self.pattern
+    pub fn get_include(&self) -> &[Pattern] {
+        &self.include
+    }
+
+    pub fn is_included(&self, file: &str) -> bool {
+        //! Returns whether a file is included by the patterns to search and
+        //! not excluded by the patterns to exclude (see pattern::find_match).
+        let is_included = self.include.is_empty()
+            || pattern::find_match(&self.include, file) == Some(true);
+
+        is_included && pattern::find_match(&self.exclude, file) != Some(true)
+    }
+
+    pub fn is_changed(&self, file: &str) -> bool {
+        //! Returns whether lines were added to a file since the base
+        //! revision, or true if every file should be searched.
+        match self.since {
+            Some((_, ref file_to_lines)) => file_to_lines.contains_key(file),
+            None => true,
+        }
+    }
+
+    pub fn is_added(&self, file: &str, line_number: u32) -> bool {
+        //! Returns whether a line was added since the base revision,
+        //! or true if every line should be searched.
+        match self.since {
+            Some((_, ref file_to_lines)) => {
+                file_to_lines.get(file).is_some_and(|ranges| {
+                    ranges.iter().any(|range| range.contains(&line_number))
+                })
+            }
+            None => true,
+        }
+    }
+
+    pub fn get_since(&self) -> Option<&str> {
+        self.since.as_ref().map(|(base, _)| base.as_str())
+    }
+
+    pub fn get_syntax(&self) -> &Registry {
+        &self.syntax
+    }
+
+    pub fn get_markers(&self) -> &[Marker] {
+        &self.markers
+    }
+
+    pub fn get_scan_key(&self) -> &str {
+        &self.scan_key
+    }
+
+    pub fn get_labels(&self) -> Vec<String> {
+        marker::get_labels(&self.markers)
+    }
+
+    pub fn get_extra_labels(&self) -> &[String] {
+        &self.labels
+    }
+
+    pub fn get_assignees(&self) -> &[String] {
+        &self.assignees
+    }
+
+    pub fn get_login(&self, email: &str) -> Option<&str> {
+        //! Returns the user to assign to todos committed with the email.
+        self.authors
+            .get(&email.to_lowercase())
+            .map(|login| login.as_str())
+    }
+
+    pub fn get_title_template(&self, keyword: &str) -> Option<&Template> {
+        //! Returns the title template of the marker's issues, if any.
+        self.marker_templates
+            .get(keyword)
+            .and_then(|templates| templates.title.as_ref())
+            .or(self.templates.title.as_ref())
+    }
+
+    pub fn get_body_template(&self, keyword: &str) -> Option<&Template> {
+        //! Returns the body template of the marker's issues, if any.
+        self.marker_templates
+            .get(keyword)
+            .and_then(|templates| templates.body.as_ref())
+            .or(self.templates.body.as_ref())
+    }
+
+    pub fn get_remote(&self) -> Option<&Remote> {
+        self.remote.as_ref()
+    }
+
+    pub fn get_tracker(&self) -> Kind {
+        self.tracker
+    }
+
+    pub fn get_api_url(&self) -> Option<&str> {
+        self.api_url.as_deref()
+    }
+
+    pub fn get_source(&self) -> &Source {
+        &self.source
+    }
+
+    pub fn get_format(&self) -> Option<Format> {
+        self.format
+    }
+
+    pub fn get_jobs(&self) -> usize {
+        self.jobs
+    }
+
+    pub fn is_ignore_case(&self) -> bool {
+        self.is_ignore_case
+    }
+
+    pub fn is_write_back(&self) -> bool {
+        self.is_write_back
+    }
+
+    pub fn is_non_interactive(&self) -> bool {
+        self.is_non_interactive
+    }
+
+    pub fn is_sync(&self) -> bool {
+        self.is_sync
     }
 
     pub fn is_dry_run(&self) -> bool {
         self.is_dry_run
     }
 
+    pub fn is_offline(&self) -> bool {
+        //! Returns whether the remote repository should not be contacted,
+        //! either because of a dry run or because the scan is being output
+        //! without a token or remote.
+        self.is_dry_run
+            || (self.format.is_some()
+                && (self.token.is_empty() || self.remote.is_none()))
+    }
+
     pub fn is_verbose(&self) -> bool {
         self.is_verbose
     }
@@ -47,23 +278,178 @@ impl Args {
 pub fn init() -> Option<Args> {
     //! Initializes the CLI and parses command line arguments.
     //!
+    //! Settings from the repository's config file are used for every flag
+    //! that is not passed explicitly. The current directory is changed to
+    //! the root of the repository, so every path is relative to it.
     //! Returns an Option containing the Args as a struct or None
-    //! if the user is not in a git repo.
+    //! if the user is not in a git repo or the arguments are invalid.
     let matches = App::new("todo-to-issue")
         .version("0.1.1")
         .author("Sergei Chestakov <sergei332@gmail.com>")
-        .about("Converts TODO comments into GitHub issues")
+        .about("Converts TODO comments into GitHub or GitLab issues")
         .arg(
             Arg::with_name("token")
                 .help("Sets the token for user")
                 .index(1),
         )
         .arg(
-            Arg::with_name("pattern")
+            Arg::with_name("include")
                 .short("p")
-                .long("pattern")
+                .long("include")
+                .alias("pattern")
+                .value_name("PATTERN")
+                .help(
+                    "Only searches the files matching a gitignore-style \
+                     pattern. Can be repeated",
+                )
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1),
+        )
+        .arg(
+            Arg::with_name("exclude")
+                .short("x")
+                .long("exclude")
                 .value_name("PATTERN")
-                .help("Sets a glob pattern to narrow search for TODO comments")
+                .help(
+                    "Never searches the files matching a gitignore-style \
+                     pattern. Can be repeated",
+                )
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1),
+        )
+        .arg(
+            Arg::with_name("syntax")
+                .short("s")
+                .long("syntax")
+                .value_name("EXTENSION=DELIMITERS")
+                .help(
+                    "Sets the comment delimiters for a file extension or name \
+                     (e.g. \"jsonnet=//,/* */\")",
+                )
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1),
+        )
+        .arg(
+            Arg::with_name("marker")
+                .short("m")
+                .long("marker")
+                .value_name("KEYWORD[=LABEL]")
+                .help(
+                    "Sets a keyword to search for and the label of its \
+                     issues (defaults to TODO)",
+                )
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1),
+        )
+        .arg(
+            Arg::with_name("ignore-case")
+                .short("i")
+                .long("ignore-case")
+                .help("Matches marker keywords case insensitively"),
+        )
+        .arg(
+            Arg::with_name("source")
+                .long("source")
+                .value_name("SOURCE")
+                .possible_values(command::SOURCES)
+                .help(
+                    "Searches the files in the index or the whole working \
+                     tree, including untracked files (defaults to index)",
+                )
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("rev")
+                .long("rev")
+                .value_name("REVISION")
+                .conflicts_with("source")
+                .help("Searches the files of a commit without checking it out")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("since")
+                .long("since")
+                .value_name("REVISION")
+                .help(
+                    "Only searches for TODOs added since the revision, e.g. \
+                     the base branch of a pull request",
+                )
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("write-back")
+                .short("w")
+                .long("write-back")
+                .help(
+                    "Adds the issue number to each comment after opening its \
+                     issue, e.g. TODO(#123)",
+                ),
+        )
+        .arg(
+            Arg::with_name("remote")
+                .short("r")
+                .long("remote")
+                .value_name("NAME")
+                .help(
+                    "Sets the git remote to open issues in (defaults to \
+                     origin)",
+                )
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("api-url")
+                .long("api-url")
+                .value_name("URL")
+                .help(
+                    "Sets the base url of the GitHub or GitLab API (defaults \
+                     to the one of the remote's host)",
+                )
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("config")
+                .short("c")
+                .long("config")
+                .value_name("FILE")
+                .help(
+                    "Sets the config file to read (defaults to \
+                     .todo-to-issue.toml in the repository root)",
+                )
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("yes")
+                .short("y")
+                .long("yes")
+                .visible_alias("non-interactive")
+                .help(
+                    "Opens an issue for every new TODO without prompting, \
+                     reading the token from GITHUB_TOKEN or GITLAB_TOKEN",
+                ),
+        )
+        .arg(
+            Arg::with_name("sync")
+                .long("sync")
+                .conflicts_with_all(&["dry-run", "format", "since"])
+                .help(
+                    "Closes the open issues whose TODO has been removed from \
+                     the code, after prompting unless --yes is passed",
+                ),
+        )
+        .arg(
+            Arg::with_name("format")
+                .short("f")
+                .long("format")
+                .value_name("FORMAT")
+                .possible_values(report::FORMATS)
+                .help(
+                    "Outputs every TODO in the given format without opening \
+                     any issues",
+                )
                 .takes_value(true),
         )
         .arg(
@@ -72,57 +458,358 @@ pub fn init() -> Option<Args> {
                 .long("dry-run")
                 .help("Outputs the number of TODOs without opening any issues"),
         )
+        .arg(
+            Arg::with_name("jobs")
+                .short("j")
+                .long("jobs")
+                .value_name("N")
+                .help(
+                    "Sets the number of files searched at once (defaults to \
+                     the number of CPUs)",
+                )
+                .takes_value(true),
+        )
         .arg(
             Arg::with_name("verbose")
                 .short("v")
                 .long("verbose")
                 .help("Makes output more descriptive"),
         )
-        .get_matches();
+        .get_matches_safe();
+    let matches = match matches {
+        Ok(matches) => matches,
+        // Help and version are printed to stdout and exit successfully.
+        Err(e) if !e.use_stderr() => e.exit(),
+        Err(e) => {
+            eprintln!("{}", e.message);
+            return None;
+        }
+    };
 
     if !command::is_git_repo() {
         print_error("Must be in a git repository.");
         return None;
     }
 
-    let is_dry_run = matches.is_present("dry-run");
-    let is_verbose = matches.is_present("verbose");
+    let config_path = match matches.value_of("config") {
+        Some(path) => PathBuf::from(path),
+        None => PathBuf::from(command::get_repo_root()).join(config::FILE_NAME),
+    };
+    let is_required = matches.is_present("config");
+    let config = match Config::load(&config_path, is_required) {
+        Ok(config) => config,
+        Err(msg) => {
+            print_error(&msg);
+            return None;
+        }
+    };
 
-    let pattern_value = matches.value_of("pattern").unwrap_or(ALL_FILES);
+    // Git lists files relative to the current directory, while the paths in
+    // issues, fingerprints and the state must not depend on where the tool
+    // is run from.
+    if let Err(error) = env::set_current_dir(command::get_repo_root()) {
+        print_error(&format!(
+            "Unable to enter the root of the repository ({}).",
+            error
+        ));
+        return None;
+    }
 
-    let pattern = match Pattern::new(pattern_value) {
-        Ok(pattern) => pattern,
-        Err(_) => Pattern::new(ALL_FILES).unwrap(),
-    };
+    match Args::from_matches(&matches, config) {
+        Ok(args) => Some(args),
+        Err(msg) => {
+            print_error(&msg);
+            None
+        }
+    }
+}
 
-    let token = match matches.value_of("token") {
-        Some(t) => t.to_string(),
-        None => match is_dry_run {
-            true => String::new(),
-            false => command::read_access_token(),
-        },
-    };
+impl Args {
+    fn from_matches(
+        matches: &ArgMatches,
+        config: Config,
+    ) -> Result<Args, String> {
+        //! Merges the command line arguments with the config file,
+        //! preferring the former.
+        //!
+        //! Returns an error message if any of the values are invalid.
+        let is_dry_run = matches.is_present("dry-run");
+        let is_non_interactive = matches.is_present("yes");
+        let is_sync = matches.is_present("sync");
+        let format = matches.value_of("format").and_then(|f| f.parse().ok());
+        if format.is_some() {
+            IS_QUIET.store(true, Ordering::Relaxed);
+        }
+        let is_verbose = matches.is_present("verbose");
+        let jobs = match matches.value_of("jobs") {
+            Some(value) => value.parse().map_err(|_| {
+                format!(
+                    "Invalid number of jobs \"{}\". Expected a number.",
+                    value
+                )
+            })?,
+            None => 0,
+        };
+        let is_ignore_case = matches.is_present("ignore-case")
+            || config.ignore_case.unwrap_or(false);
+        let is_write_back = matches.is_present("write-back")
+            || config.write_back.unwrap_or(false);
 
-    return Some(Args {
-        pattern,
-        token,
-        is_dry_run,
-        is_verbose,
-    });
+        let source = match (matches.value_of("rev"), matches.value_of("source"))
+        {
+            (Some(revision), _) => {
+                if !command::is_valid_revision(revision) {
+                    return Err(format!("Unknown revision \"{}\".", revision));
+                }
+                if is_write_back {
+                    return Err(
+                        "Write back cannot be used when searching a revision."
+                            .to_string(),
+                    );
+                }
+                Source::Revision(revision.to_string())
+            }
+            (None, Some(value)) => value.parse()?,
+            (None, None) => match config.source {
+                Some(ref value) => value.parse()?,
+                None => Source::Index,
+            },
+        };
+
+        let since = match matches.value_of("since") {
+            Some(base) => {
+                if !command::is_valid_revision(base) {
+                    return Err(format!("Unknown revision \"{}\".", base));
+                }
+                match command::get_added_lines(base, &source) {
+                    Some(file_to_lines) => {
+                        Some((base.to_string(), file_to_lines))
+                    }
+                    None => {
+                        return Err(format!(
+                            "Unable to find the changes since \"{}\".",
+                            base
+                        ))
+                    }
+                }
+            }
+            None => None,
+        };
+
+        let include = match matches.values_of("include") {
+            Some(values) => values
+                .map(Pattern::parse)
+                .collect::<Result<Vec<Pattern>, String>>()?,
+            None => pattern::parse_all(&config.include.unwrap_or_default())?,
+        };
+        // Later patterns take precedence, so flags can include files again
+        // that are excluded by the config or the ignore file.
+        let mut exclude =
+            pattern::parse_all(&config.exclude.unwrap_or_default())?;
+        let ignore_file =
+            PathBuf::from(command::get_repo_root()).join(pattern::IGNORE_FILE);
+        exclude.extend(pattern::read_ignore_file(&ignore_file)?);
+        for value in matches.values_of("exclude").into_iter().flatten() {
+            exclude.push(Pattern::parse(value)?);
+        }
+
+        let mut syntax = Registry::new();
+        // Everything that changes how files are scanned, see get_scan_key.
+        let mut scan_settings = vec![
+            env!("CARGO_PKG_VERSION").to_string(),
+            is_ignore_case.to_string(),
+        ];
+        for (key, delimiters) in config.syntax.unwrap_or_default() {
+            let custom = Syntax::from_delimiters(&delimiters)
+                .map_err(|e| format!("{} for \"{}\".", e, key))?;
+            syntax.insert(&key, custom);
+            scan_settings.push(format!("{}={}", key, delimiters.join(" ")));
+        }
+        for value in matches.values_of("syntax").into_iter().flatten() {
+            let (key, custom) = parse_syntax(value)?;
+            syntax.insert(&key, custom);
+            scan_settings.push(value.to_string());
+        }
+
+        let mut markers = Vec::new();
+        for value in matches.values_of("marker").into_iter().flatten() {
+            markers.push(Marker::from_string(value)?);
+        }
+        if markers.is_empty() {
+            for (keyword, label) in config.markers.unwrap_or_default() {
+                let value = format!("{}={}", keyword, label);
+                markers.push(Marker::from_string(&value)?);
+            }
+        }
+        if markers.is_empty() {
+            let keyword = marker::DEFAULT_KEYWORD;
+            markers.push(Marker::new(keyword, keyword));
+        }
+        for marker in &markers {
+            scan_settings.push(format!(
+                "{}={}",
+                marker.get_keyword(),
+                marker.get_label()
+            ));
+        }
+        let scan_key = fingerprint::hash(scan_settings.join("\0").as_bytes());
+
+        let remote_name = matches
+            .value_of("remote")
+            .or(config.remote.as_deref())
+            .unwrap_or(command::DEFAULT_REMOTE);
+        let remote = match command::get_remote_url(remote_name)
+            .and_then(|url| Remote::parse(&url))
+        {
+            Some(remote) => Some(remote),
+            None if is_dry_run || format.is_some() => {
+                print_warning("No remote found. Searching for TODOs anyways.");
+                None
+            }
+            None => {
+                return Err(format!(
+                    "No remote named \"{}\" with a supported url found.",
+                    remote_name
+                ))
+            }
+        };
+        let tracker = match config.tracker {
+            Some(ref value) => value.parse()?,
+            None => match remote {
+                Some(ref remote) => Kind::from_host(&remote.host),
+                None => Kind::GitHub,
+            },
+        };
+        let api_url = match matches.value_of("api-url") {
+            Some(value) => Some(parse_api_url(value)?),
+            None => match config.api_url {
+                Some(ref value) => Some(parse_api_url(value)?),
+                None => None,
+            },
+        };
+
+        let templates = Templates::parse(
+            config.title_template.as_deref(),
+            config.template.as_deref(),
+            None,
+        )?;
+        let mut marker_templates = HashMap::new();
+        for (keyword, marker) in config.templates.unwrap_or_default() {
+            if !markers.iter().any(|m| m.get_keyword() == keyword) {
+                return Err(format!(
+                    "Templates set for unknown marker \"{}\".",
+                    keyword
+                ));
+            }
+            let parsed = Templates::parse(
+                marker.title.as_deref(),
+                marker.body.as_deref(),
+                Some(&keyword),
+            )?;
+            marker_templates.insert(keyword, parsed);
+        }
+
+        let token_variable = tracker.get_token_variable();
+        let token = match matches.value_of("token") {
+            Some(t) => t.to_string(),
+            None => match env::var(token_variable) {
+                Ok(t) => t,
+                Err(_) if is_dry_run || format.is_some() => String::new(),
+                Err(_) if is_non_interactive => {
+                    return Err(format!(
+                        "A token must be passed or set in {} when running \
+                         non-interactively.",
+                        token_variable
+                    ))
+                }
+                Err(_) => command::read_access_token(tracker.get_name()),
+            },
+        };
+
+        Ok(Args {
+            include,
+            exclude,
+            token,
+            syntax,
+            markers,
+            scan_key,
+            labels: config.labels.unwrap_or_default(),
+            assignees: config.assignees.unwrap_or_default(),
+            authors: config
+                .authors
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(email, login)| (email.to_lowercase(), login))
+                .collect(),
+            templates,
+            marker_templates,
+            remote,
+            tracker,
+            api_url,
+            source,
+            since,
+            format,
+            jobs,
+            is_ignore_case,
+            is_write_back,
+            is_non_interactive,
+            is_sync,
+            is_dry_run,
+            is_verbose,
+        })
+    }
+
+    #[cfg(test)]
+    pub fn with_defaults() -> Args {
+        //! Returns the arguments of a dry run with the default settings,
+        //! without reading the repository or its config file.
+        let marker = marker::DEFAULT_KEYWORD;
+        Args {
+            include: Vec::new(),
+            exclude: Vec::new(),
+            token: String::new(),
+            syntax: Registry::new(),
+            markers: vec![Marker::new(marker, marker)],
+            scan_key: String::new(),
+            labels: Vec::new(),
+            assignees: Vec::new(),
+            authors: HashMap::new(),
+            templates: Templates::parse(None, None, None).unwrap(),
+            marker_templates: HashMap::new(),
+            remote: None,
+            tracker: Kind::GitHub,
+            api_url: None,
+            source: Source::Index,
+            since: None,
+            format: None,
+            jobs: 1,
+            is_ignore_case: false,
+            is_write_back: false,
+            is_non_interactive: false,
+            is_sync: false,
+            is_dry_run: true,
+            is_verbose: false,
+        }
+    }
 }
 
 pub fn output_issues_and_prompt_user(
-    request: &Request,
-    map: &HashMap<String, Vec<Issue>>,
-) {
+    tracker: &dyn Tracker,
+    map: &BTreeMap<String, Vec<Issue>>,
+    state: &mut State,
+    args: &Args,
+) -> i32 {
     //! Outputs every todo comment found and prompts the user for action.
     //!
     //! Allows the user to
-    //! - Open a GitHub issue
+    //! - Open an issue
     //! - Edit the body or title before opening
     //! - Skip to the next one
+    //! - Ignore it in this and future runs
     //! - Exit the program
-    for (_file, issues) in map {
+    //!
+    //! Returns the exit code of the program.
+    for issues in map.values() {
         for issue in issues {
             println!("\n{}", &issue.to_formatted_string());
 
@@ -132,39 +819,278 @@ pub fn output_issues_and_prompt_user(
             let selection = Select::with_theme(&ColorfulTheme::default())
                 .with_prompt(&prompt)
                 .default(0)
-                .items(&SELECTIONS[..])
+                .items(SELECTIONS)
                 .interact()
                 .unwrap();
 
-            let done = match selection {
-                OPEN => open_issue(&request, &issue),
-                EDIT => edit_issue(&request, &issue),
-                SKIP => false,
-                _ => true,
+            let exit_code = match selection {
+                OPEN => open_issue(tracker, issue, state, args),
+                EDIT => edit_issue(tracker, issue, state, args),
+                SKIP => None,
+                IGNORE => {
+                    ignore_issue(issue, state);
+                    None
+                }
+                _ => Some(EXIT_SUCCESS),
             };
-            if done {
-                return;
+            if let Some(exit_code) = exit_code {
+                return exit_code;
             }
         }
     }
 
     print_success("All done!");
+    EXIT_SUCCESS
+}
+
+pub fn close_removed_issues(
+    tracker: &dyn Tracker,
+    issues: &[&Opened],
+    summary: &mut Summary,
+    state: &mut State,
+    args: &Args,
+) {
+    //! Closes every issue whose todo has been removed from the code with a
+    //! comment linking to the commit that removed it, prompting for each one
+    //! unless running non-interactively.
+    //!
+    //! Adds the closed issues and the ones that failed to the summary.
+    if issues.is_empty() {
+        print_info(
+            "No issues to close. Every one of them is still in the code.",
+        );
+        return;
+    }
+
+    for issue in issues {
+        let entry = json!({ "title": issue.title, "number": issue.number });
+        if !args.is_non_interactive() {
+            let prompt = format!(
+                "The TODO of issue #{} \"{}\" was removed. Close it?",
+                issue.number, issue.title
+            );
+            let is_confirmed = Confirmation::new()
+                .with_text(&prompt)
+                .default(false)
+                .interact()
+                .unwrap_or(false);
+            if !is_confirmed {
+                continue;
+            }
+        }
+
+        // The title may have been edited or come from a template, so the
+        // text of the comment is searched for if it was seen before.
+        let text = state.get_comment(issue.number).unwrap_or(&issue.title);
+        let file = issue.file.as_deref().unwrap_or(".");
+        let commit =
+            command::find_removing_commit(text, file, args.get_source());
+        let comment = match (commit, args.get_remote()) {
+            (Some(commit), Some(remote)) => format!(
+                "The TODO this issue was opened for was removed in {}",
+                args.get_tracker().get_commit_url(remote, &commit)
+            ),
+            _ => "The TODO this issue was opened for is no longer in the code."
+                .to_string(),
+        };
+        if tracker.comment_on_issue(issue.number, &comment)
+            && tracker.set_issue_state(issue.number, false)
+        {
+            print_success(&format!(
+                "Closed issue #{}: \"{}\"",
+                issue.number, issue.title
+            ));
+            state.set_issue_state(issue.number, false);
+            summary.closed.push(entry);
+        } else {
+            summary.failed.push(entry);
+        }
+    }
+}
+
+pub fn handle_closed_issues(
+    tracker: &dyn Tracker,
+    todos: &[&Todo],
+    summary: &mut Summary,
+    state: &mut State,
+    args: &Args,
+) -> Option<i32> {
+    //! Goes through every todo that is still in the code although its issue
+    //! was closed, prompting the user to
+    //! - Reopen the issue
+    //! - Comment on the issue
+    //! - Skip to the next one
+    //! - Exit the program
+    //!
+    //! Both reopening and commenting leave a comment pointing to the todo.
+    //! Issues are only listed in the summary when running
+    //! non-interactively.
+    //! Returns the exit code if the program should terminate.
+    for todo in todos {
+        let number = match todo.issue {
+            Some(number) => number,
+            None => continue,
+        };
+        let entry = json!({
+            "title": todo.title,
+            "number": number,
+            "file": todo.file,
+            "line": todo.line_number,
+        });
+        if args.is_non_interactive() {
+            summary.still_present.push(entry);
+            continue;
+        }
+
+        println!(
+            "\n{} {}",
+            style(format!(
+                "Issue #{} was closed, but its TODO is still in",
+                number
+            ))
+            .yellow(),
+            style(format!("{}:{}", todo.file, todo.line_number)).bold()
+        );
+        println!("{}", todo.title);
+        let prompt =
+            format!("{}", style("What would you like to do?").italic());
+        let selection = Select::with_theme(&ColorfulTheme::default())
+            .with_prompt(&prompt)
+            .default(0)
+            .items(CLOSED_SELECTIONS)
+            .interact()
+            .unwrap();
+
+        let location = todo
+            .get_permalink(args)
+            .unwrap_or_else(|| format!("{}:{}", todo.file, todo.line_number));
+        let comment = format!(
+            "The TODO this issue was opened for is still in the code: {}",
+            location
+        );
+        match selection {
+            REOPEN => {
+                if tracker.comment_on_issue(number, &comment)
+                    && tracker.set_issue_state(number, true)
+                {
+                    print_success(&format!(
+                        "Reopened issue #{}: \"{}\"",
+                        number, todo.title
+                    ));
+                    state.set_issue_state(number, true);
+                    summary.reopened.push(entry);
+                } else {
+                    summary.failed.push(entry);
+                }
+            }
+            COMMENT => {
+                if tracker.comment_on_issue(number, &comment) {
+                    print_success(&format!(
+                        "Commented on issue #{}: \"{}\"",
+                        number, todo.title
+                    ));
+                    summary.commented.push(entry);
+                } else {
+                    summary.failed.push(entry);
+                }
+            }
+            SKIP => summary.still_present.push(entry),
+            _ => return Some(EXIT_SUCCESS),
+        }
+    }
+
+    None
+}
+
+pub fn open_issues_without_prompt(
+    tracker: &dyn Tracker,
+    map: &BTreeMap<String, Vec<Issue>>,
+    summary: Summary,
+    state: &mut State,
+    args: &Args,
+) -> i32 {
+    //! Opens an issue for every todo comment found without prompting,
+    //! carrying on if any of them fail.
+    //!
+    //! Finishes by printing a JSON summary of the opened, closed (see
+    //! close_removed_issues), still present (see handle_closed_issues) and
+    //! failed issues on a single line and returns the exit code of the
+    //! program.
+    let Summary {
+        closed,
+        reopened,
+        commented,
+        still_present,
+        mut failed,
+        ignored,
+    } = summary;
+    let mut opened = Vec::new();
+    for issues in map.values() {
+        for issue in issues {
+            let mut entry = to_summary_entry(issue);
+            match create_issue(tracker, issue, state, args) {
+                Some(issue_number) => {
+                    entry["number"] = json!(issue_number);
+                    opened.push(entry);
+                }
+                None => failed.push(entry),
+            }
+        }
+    }
+
+    let summary = json!({
+        "found": map.values().map(|issues| issues.len()).sum::<usize>(),
+        "opened": opened,
+        "closed": closed,
+        "reopened": reopened,
+        "commented": commented,
+        "still_present": still_present,
+        "failed": failed,
+        "ignored": ignored,
+    });
+    println!("{}", summary);
+
+    match (opened.is_empty() && closed.is_empty(), failed.is_empty()) {
+        (_, false) => EXIT_ERROR,
+        (false, true) => EXIT_OPENED,
+        (true, true) => EXIT_SUCCESS,
+    }
+}
+
+pub fn is_quiet() -> bool {
+    IS_QUIET.load(Ordering::Relaxed)
+}
+
+pub fn print_info(msg: &str) {
+    if !is_quiet() {
+        println!("{}", msg);
+    }
 }
 
 pub fn print_success(msg: &str) {
-    println!("{}", style(msg).green());
+    if !is_quiet() {
+        println!("{}", style(msg).green());
+    }
 }
 
 pub fn print_warning(msg: &str) {
-    println!("{}", style(msg).yellow());
+    if !is_quiet() {
+        println!("{}", style(msg).yellow());
+    }
 }
 
 pub fn print_error(msg: &str) {
-    println!("{} {}", style("ERROR:").red(), style(msg).red());
+    let error = format!("{} {}", style("ERROR:").red(), style(msg).red());
+    match is_quiet() {
+        true => eprintln!("{}", error),
+        false => println!("{}", error),
+    }
 }
 
 pub fn print_dim(msg: &str) {
-    println!("{}", style(msg).dim());
+    if !is_quiet() {
+        println!("{}", style(msg).dim());
+    }
 }
 
 pub fn handle_plural(number: &usize, word: &str) -> String {
@@ -174,20 +1100,73 @@ pub fn handle_plural(number: &usize, word: &str) -> String {
     }
 }
 
-fn edit_issue(request: &Request, issue: &Issue) -> bool {
+fn parse_api_url(value: &str) -> Result<String, String> {
+    //! Validates the base url of an API, returning it without a trailing
+    //! slash so paths can be appended to it.
+    match Url::parse(value) {
+        Ok(ref url) if url.scheme() == "http" || url.scheme() == "https" => {
+            Ok(value.trim_end_matches('/').to_string())
+        }
+        _ => Err(format!(
+            "Invalid API url \"{}\". Expected e.g. https://ghe.example.com/api/v3",
+            value
+        )),
+    }
+}
+
+fn parse_syntax(value: &str) -> Result<(String, Syntax), String> {
+    //! Parses a custom comment syntax of the form
+    //! EXTENSION=DELIMITER[,DELIMITER...]
+    //!
+    //! Returns the extension (or file name) and the syntax it maps to.
+    let split: Vec<&str> = value.splitn(2, '=').collect();
+    if split.len() < 2 || split[0].trim().is_empty() {
+        return Err(format!(
+            "Invalid syntax \"{}\". Expected EXTENSION=DELIMITERS.",
+            value
+        ));
+    }
+
+    let delimiters: Vec<String> =
+        split[1].split(',').map(|d| d.trim().to_string()).collect();
+    let syntax = Syntax::from_delimiters(&delimiters)
+        .map_err(|e| format!("{} for \"{}\".", e, split[0]))?;
+
+    Ok((split[0].trim().to_string(), syntax))
+}
+
+fn to_summary_entry(issue: &Issue) -> Value {
+    //! Describes an issue for the non-interactive summary.
+    match issue.get_location() {
+        Some(location) => json!({
+            "title": issue.get_title(),
+            "file": location.file,
+            "line": location.line_number,
+        }),
+        None => json!({ "title": issue.get_title() }),
+    }
+}
+
+fn edit_issue(
+    tracker: &dyn Tracker,
+    issue: &Issue,
+    state: &mut State,
+    args: &Args,
+) -> Option<i32> {
     //! Opens the user's default editor and allows them to edit an issue's
     //! title and body before opening it.
     //!
-    //! Creates an issue on GitHub if the format is valid
+    //! Creates an issue in the tracker if the format is valid
     //! (see Issue::from_string) and the user saves and quits.
+    //! The edited issue keeps the labels of the original.
     //! Aborts the operation if the user exits without saving.
-    //! Returns a bool indicating whether or not to terminate the program.
+    //! Returns the exit code if the program should terminate.
     let result = Editor::new().edit(&issue.to_string()).unwrap();
 
     match result {
-        Some(input) => match Issue::from_string(input) {
+        Some(input) => match issue.edit(input) {
             Some(new_issue) => {
-                return open_issue(request, &new_issue);
+                return open_issue(tracker, &new_issue, state, args);
             }
             None => print_warning("Invalid format. Not creating issue."),
         },
@@ -196,24 +1175,87 @@ fn edit_issue(request: &Request, issue: &Issue) -> bool {
         }
     }
 
-    false
+    None
 }
 
-fn open_issue(request: &Request, issue: &Issue) -> bool {
-    //! Creates the GitHub issue and outputs the result.
-    //! Returns a bool indicating whether or not to terminate the program.
-    match request.open_issue(issue) {
-        Some(issue_number) => {
-            let success_msg = format!(
-                "Successfully opened issue #{}: \"{}\"",
-                issue_number,
-                issue.get_title()
-            )
-            .to_string();
+fn open_issue(
+    tracker: &dyn Tracker,
+    issue: &Issue,
+    state: &mut State,
+    args: &Args,
+) -> Option<i32> {
+    //! Creates the issue, terminating the program if it fails.
+    //! Returns the exit code if the program should terminate.
+    match create_issue(tracker, issue, state, args) {
+        Some(_) => None,
+        None => Some(EXIT_ERROR),
+    }
+}
+
+fn create_issue(
+    tracker: &dyn Tracker,
+    issue: &Issue,
+    state: &mut State,
+    args: &Args,
+) -> Option<usize> {
+    //! Creates the issue and outputs the result.
+    //! References the new issue in the source comment if write back is on.
+    //! The issue is remembered so offline runs know it has been opened.
+    //! Returns the issue number if successful.
+    let issue_number = tracker.open_issue(issue)?;
+    state.add_issue(Opened {
+        title: issue.get_title(),
+        number: issue_number,
+        fingerprint: issue.get_fingerprint().map(|f| f.to_string()),
+        file: issue.get_location().map(|location| location.file.clone()),
+        is_open: true,
+    });
+    if let Some(location) = issue.get_location() {
+        state.set_comment(issue_number, &location.text);
+    }
+    let success_msg = format!(
+        "Successfully opened issue #{}: \"{}\"",
+        issue_number,
+        issue.get_title()
+    )
+    .to_string();
 
-            print_success(&success_msg);
-            false
+    print_success(&success_msg);
+    if args.is_write_back() {
+        write_reference(issue, issue_number);
+    }
+
+    Some(issue_number)
+}
+
+fn ignore_issue(issue: &Issue, state: &mut State) {
+    //! Remembers the todo of the issue so it is not offered again.
+    match issue.get_fingerprint() {
+        Some(fingerprint) => {
+            state.ignore(fingerprint);
+            print_dim("Ignoring this TODO in future runs.");
         }
-        None => true,
+        None => print_warning("Unable to ignore this TODO in future runs."),
+    }
+}
+
+fn write_reference(issue: &Issue, issue_number: usize) {
+    //! Writes the issue number back into the comment the issue was
+    //! created from, warning the user if the file could not be updated.
+    let location = match issue.get_location() {
+        Some(location) => location,
+        None => return,
+    };
+
+    match parse::write_reference(location, issue_number) {
+        Ok(()) => print_dim(&format!(
+            "Added reference to line {} of {}",
+            location.line_number, location.file
+        )),
+        Err(_) => print_warning(&format!(
+            "Unable to add reference to line {} of {}. \
+             Has the file changed?",
+            location.line_number, location.file
+        )),
     }
 }
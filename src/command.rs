@@ -1,13 +1,104 @@
-use std::process::Command;
-use std::str;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Write};
+use std::ops::{Deref, Range};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::str::{self, FromStr};
+use std::thread;
 
 use dialoguer::PasswordInput;
+use memmap2::Mmap;
+use serde::{Deserialize, Serialize};
 
-use super::cli;
+pub const DEFAULT_REMOTE: &str = "origin";
+pub const SOURCES: &[&str] = &["index", "worktree"];
+/// Size in bytes from which files are mapped into memory instead of read.
+const MMAP_THRESHOLD: u64 = 1024 * 1024;
+/// Attributes that mark files not worth searching, e.g. minified or
+/// third-party code.
+const SKIPPED_ATTRIBUTES: &[&str] =
+    &["binary", "linguist-generated", "linguist-vendored"];
+/// Author `git blame` reports for lines that have not been committed.
+const UNCOMMITTED_AUTHOR: &str = "Not Committed Yet";
 
-pub fn read_access_token() -> String {
-    //! Reads in a user's personal access token from GitHub.
-    println!("Please paste your personal access token from GitHub below.");
+/// Who last changed a line according to `git blame`.
+#[derive(Clone, Default, Deserialize, Serialize)]
+pub struct Blame {
+    pub author: String,
+    pub email: String,
+    pub commit: String,
+    /// Date of the commit in the author's time zone, e.g. 2019-07-28
+    pub date: String,
+    /// Path of the file in the commit, relative to the repository root.
+    pub path: String,
+    /// Number of the line in the commit.
+    pub line_number: u32,
+}
+
+/// Maps file paths to the ranges of line numbers added to them.
+pub type AddedLines = HashMap<String, Vec<Range<u32>>>;
+
+/// The contents of a file, see read_file.
+pub enum Contents {
+    Read(Vec<u8>),
+    Mapped(Mmap),
+}
+
+impl Deref for Contents {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            Contents::Read(bytes) => bytes,
+            Contents::Mapped(mmap) => mmap,
+        }
+    }
+}
+
+/// Where the files to search are read from.
+pub enum Source {
+    /// Files in the index (staged or committed), read from the working tree.
+    Index,
+    /// Like the index, plus untracked files that are not ignored.
+    Worktree,
+    /// Files of a commit or tree, read without checking it out.
+    Revision(String),
+}
+
+impl FromStr for Source {
+    type Err = String;
+
+    fn from_str(string: &str) -> Result<Source, String> {
+        match string {
+            "index" => Ok(Source::Index),
+            "worktree" => Ok(Source::Worktree),
+            _ => Err(format!(
+                "Invalid source \"{}\". Expected one of {}.",
+                string,
+                SOURCES.join(", ")
+            )),
+        }
+    }
+}
+
+impl Source {
+    pub fn describe(&self) -> String {
+        //! Returns a human readable description of the files searched.
+        match self {
+            Source::Index => "tracked by git".to_string(),
+            Source::Worktree => "in the working tree".to_string(),
+            Source::Revision(revision) => format!("in {}", revision),
+        }
+    }
+}
+
+pub fn read_access_token(service: &str) -> String {
+    //! Reads in a user's personal access token from the given service.
+    println!(
+        "Please paste your personal access token from {} below.",
+        service
+    );
     PasswordInput::new()
         .with_prompt("Token")
         .interact()
@@ -25,72 +116,628 @@ pub fn is_git_repo() -> bool {
     !output.is_empty()
 }
 
-pub fn get_remote_name(is_dry_run: bool) -> Option<String> {
-    //! Executes the command `git remote get-url origin`.
-    //! Parses the result to return a string of the form :username/:repo
-    //! if successful. Otherwise, returns None if there is no remote.
+pub fn get_repo_root() -> String {
+    //! Executes the command `git rev-parse --show-toplevel`.
+    //! Returns the output which represents the root of the repository.
+    let command = Command::new("git")
+        .arg("rev-parse")
+        .arg("--show-toplevel")
+        .output()
+        .expect("Failed to execute `git rev-parse --show-toplevel`");
+    let output = str::from_utf8(&command.stdout).unwrap();
+
+    output.trim().to_string()
+}
+
+pub fn get_git_path(path: &str) -> Option<PathBuf> {
+    //! Executes the command `git rev-parse --git-path {path}`.
+    //! Returns where the file belongs in the git directory, which is never
+    //! committed, or None if it could not be found.
+    let command = Command::new("git")
+        .arg("rev-parse")
+        .arg("--git-path")
+        .arg(path)
+        .output()
+        .expect("Failed to execute `git rev-parse --git-path`");
+    let output = str::from_utf8(&command.stdout).ok()?.trim();
+
+    match command.status.success() && !output.is_empty() {
+        true => Some(PathBuf::from(output)),
+        false => None,
+    }
+}
+
+pub fn get_remote_url(remote: &str) -> Option<String> {
+    //! Executes the command `git remote get-url {remote}`.
+    //! Returns the url of the remote or None if there is no such remote.
     let command = Command::new("git")
         .arg("remote")
         .arg("get-url")
-        .arg("origin")
+        .arg(remote)
         .output()
-        .expect("Failed to execute `git remote get-url origin`");
-    let output = str::from_utf8(&command.stdout).unwrap();
-    // Output is of the form https://github.com/:username/:repo.git
-    // So we must remove the protocol/domain and .git suffix.
-    let split: Vec<&str> = output.split("github.com/").collect();
-    if split.len() < 2 {
-        match is_dry_run {
-            true => {
-                cli::print_warning(
-                    "No remote found. Searching for TODOs anyways.",
-                );
-                return Some(String::new());
-            }
-            false => {
-                cli::print_error("No remote found.");
-                return None;
+        .expect("Failed to execute `git remote get-url`");
+    let output = str::from_utf8(&command.stdout).unwrap().trim();
+
+    match command.status.success() && !output.is_empty() {
+        true => Some(output.to_string()),
+        false => None,
+    }
+}
+
+pub fn get_files(source: &Source) -> Vec<String> {
+    //! Lists the files to search depending on the source:
+    //! - `git ls-files` for the index
+    //! - `git ls-files --others --exclude-standard` for the working tree,
+    //!   which adds untracked files that are not ignored
+    //! - `git ls-tree -r {revision}` for a commit
+    //!
+    //! Files listed in the index that have since been deleted are left out.
+    let mut command = Command::new("git");
+    match source {
+        Source::Index => command.arg("ls-files"),
+        Source::Worktree => command
+            .arg("ls-files")
+            .arg("--cached")
+            .arg("--others")
+            .arg("--exclude-standard"),
+        Source::Revision(revision) => command
+            .arg("ls-tree")
+            .arg("-r")
+            .arg("--name-only")
+            .arg(revision),
+    };
+    let output = command
+        .arg("-z")
+        .output()
+        .expect("Failed to execute `git ls-files`");
+    let output = String::from_utf8_lossy(&output.stdout);
+
+    output
+        .split('\0')
+        .filter(|file| !file.is_empty())
+        .filter(|file| match source {
+            Source::Revision(_) => true,
+            _ => Path::new(file).is_file(),
+        })
+        .map(|file| file.to_string())
+        .collect()
+}
+
+pub fn read_file(source: &Source, path: &str) -> io::Result<Contents> {
+    //! Reads the contents of a file from the working tree, mapping it into
+    //! memory if it is large, or executes the command
+    //! `git cat-file blob {revision}:./{path}` to read it from a commit
+    //! without checking it out.
+    //!
+    //! Mapped files are searched without being copied, as long as they are
+    //! valid UTF-8 (see encoding::decode).
+    let revision = match source {
+        Source::Revision(revision) => revision,
+        _ => {
+            let file = fs::File::open(path)?;
+            if file.metadata()?.len() < MMAP_THRESHOLD {
+                return fs::read(path).map(Contents::Read);
             }
+            // SAFETY: if another process truncates the file while it is
+            // mapped, reading the missing pages raises SIGBUS and kills the
+            // process. The map only lives while the file is searched, and
+            // files are not expected to be rewritten during a run.
+            let mmap = unsafe { Mmap::map(&file)? };
+            return Ok(Contents::Mapped(mmap));
         }
+    };
+
+    let output = Command::new("git")
+        .arg("cat-file")
+        .arg("blob")
+        .arg(format!("{}:./{}", revision, path))
+        .output()?;
+    match output.status.success() {
+        true => Ok(Contents::Read(output.stdout)),
+        false => Err(io::Error::from(io::ErrorKind::NotFound)),
     }
-    let remote = split[1].to_string();
-    let vec: Vec<&str> = remote.split(".git").collect();
+}
 
-    Some(vec[0].to_string())
+pub fn get_added_lines(base: &str, source: &Source) -> Option<AddedLines> {
+    //! Executes the command `git diff -U0 {base}...{revision}` when searching
+    //! a revision, and otherwise `git diff -U0 {merge base}` where the merge
+    //! base is the one of the base and HEAD, to compare with the working
+    //! tree the files are read from, including uncommitted changes.
+    //! Untracked files searched in the working tree are entirely added.
+    //! Returns a map of file path to the ranges of line numbers added in
+    //! the new version of the file, or None if a command fails.
+    let range = match source {
+        Source::Revision(revision) => format!("{}...{}", base, revision),
+        _ => get_merge_base(base)?,
+    };
+    let command = Command::new("git")
+        .arg("-c")
+        .arg("core.quotePath=false")
+        .arg("diff")
+        .arg("-U0")
+        .arg("--no-color")
+        .arg("--no-ext-diff")
+        .arg("--src-prefix=a/")
+        .arg("--dst-prefix=b/")
+        .arg(range)
+        .output()
+        .expect("Failed to execute `git diff`");
+    if !command.status.success() {
+        return None;
+    }
+    let mut file_to_lines =
+        parse_added_lines(&String::from_utf8_lossy(&command.stdout));
+
+    if let Source::Worktree = source {
+        let command = Command::new("git")
+            .arg("ls-files")
+            .arg("--others")
+            .arg("--exclude-standard")
+            .arg("-z")
+            .output()
+            .expect("Failed to execute `git ls-files`");
+        let output = String::from_utf8_lossy(&command.stdout);
+        for file in output.split('\0').filter(|file| !file.is_empty()) {
+            // Every line of an untracked file is new.
+            file_to_lines
+                .entry(file.to_string())
+                .or_default()
+                .push(1..u32::MAX);
+        }
+    }
+
+    Some(file_to_lines)
 }
 
-pub fn get_tracked_files() -> Vec<String> {
-    //! Executes the command `git ls-tree -r {branch} --name-only`.
-    //! Parses the output to return a vector of file paths that represents
-    //! all files tracked by git.
-    let branch = get_branch_name();
+fn get_merge_base(base: &str) -> Option<String> {
+    //! Executes the command `git merge-base {base} HEAD`.
+    //! Returns the hash of the best common ancestor of both, or None if
+    //! there is none.
     let command = Command::new("git")
-        .arg("ls-tree")
-        .arg("-r")
-        .arg(branch)
-        .arg("--name-only")
+        .arg("merge-base")
+        .arg(base)
+        .arg("HEAD")
         .output()
-        .expect("Failed to execute `git ls-tree -r master --name-only`");
-    let output = str::from_utf8(&command.stdout).unwrap();
-    let files: Vec<&str> = output.split("\n").collect();
+        .expect("Failed to execute `git merge-base`");
+    let output = str::from_utf8(&command.stdout).ok()?.trim();
 
-    files
-        .into_iter()
-        .map(|string| string.to_string())
-        .filter(|string| !string.is_empty())
-        .collect()
+    match command.status.success() && !output.is_empty() {
+        true => Some(output.to_string()),
+        false => None,
+    }
+}
+
+fn parse_added_lines(diff: &str) -> AddedLines {
+    //! Parses the output of `git diff -U0` into the ranges of line numbers
+    //! added to each file.
+    //!
+    //! Each file starts with a "diff --git" line and a header that contains
+    //! "+++ b/{path}", followed by hunks with headers of the form
+    //! "@@ -{old start}[,{count}] +{new start}[,{count}] @@". Added lines
+    //! may start with "++" themselves, so the path is only looked for in
+    //! the header.
+    let mut file_to_lines = AddedLines::new();
+    let mut file: Option<String> = None;
+    let mut is_header = false;
+    for line in diff.lines() {
+        if line.starts_with("diff --git ") {
+            is_header = true;
+            file = None;
+        } else if let Some(header) = line.strip_prefix("@@ ") {
+            is_header = false;
+            let file = match file {
+                Some(ref file) => file,
+                None => continue,
+            };
+            let added = header
+                .split(' ')
+                .find_map(|range| range.strip_prefix('+'))
+                .unwrap_or("");
+            let mut split = added.splitn(2, ',');
+            let start: u32 = split.next().unwrap_or("").parse().unwrap_or(0);
+            let count: u32 = split.next().unwrap_or("1").parse().unwrap_or(0);
+            if count > 0 {
+                file_to_lines
+                    .entry(file.clone())
+                    .or_default()
+                    .push(start..start + count);
+            }
+        } else if is_header {
+            if let Some(path) = line.strip_prefix("+++ ") {
+                file = path.strip_prefix("b/").map(|path| path.to_string());
+            }
+        }
+    }
+
+    file_to_lines
 }
 
-fn get_branch_name() -> String {
-    //! Executes the command `git rev-parse --abbrev-ref HEAD`.
-    //! Returns the output which represents the current branch the user is on.
+pub fn find_removing_commit(
+    text: &str,
+    file: &str,
+    source: &Source,
+) -> Option<String> {
+    //! Executes the command `git log -S {text} -n 1 {revision} -- {file}` to
+    //! find the last commit that added or removed the text in the file,
+    //! which is the one that removed it if it is no longer in the code.
+    //! Returns the hash of the commit or None if there is no such commit.
+    let revision = match source {
+        Source::Revision(revision) => revision.as_str(),
+        _ => "HEAD",
+    };
     let command = Command::new("git")
+        .arg("log")
+        .arg(format!("-S{}", text))
+        .arg("-n")
+        .arg("1")
+        .arg("--format=%H")
+        .arg(revision)
+        .arg("--")
+        .arg(file)
+        .output()
+        .expect("Failed to execute `git log -S`");
+    let output = str::from_utf8(&command.stdout).unwrap_or("").trim();
+
+    match output.is_empty() {
+        true => None,
+        false => Some(output.to_string()),
+    }
+}
+
+pub fn get_skipped_attributes(files: &[&String]) -> HashMap<String, String> {
+    //! Executes the command `git check-attr -z --stdin` with the attributes
+    //! of SKIPPED_ATTRIBUTES, which are read from the .gitattributes files
+    //! of the working tree.
+    //!
+    //! Returns the first of them that is set for each file, leaving out the
+    //! files none of them is set for.
+    let child = Command::new("git")
+        .arg("check-attr")
+        .arg("-z")
+        .arg("--stdin")
+        .args(SKIPPED_ATTRIBUTES)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn();
+    let mut child = match child {
+        Ok(child) => child,
+        Err(_) => return HashMap::new(),
+    };
+
+    // The paths are written from another thread so git never blocks on
+    // writing its output while they are still being written.
+    let mut input = Vec::new();
+    for file in files {
+        input.extend_from_slice(file.as_bytes());
+        input.push(0);
+    }
+    let mut stdin = child.stdin.take().expect("Failed to open stdin");
+    let writer = thread::spawn(move || stdin.write_all(&input));
+    let output = match child.wait_with_output() {
+        Ok(output) => output,
+        Err(_) => return HashMap::new(),
+    };
+    let _ = writer.join();
+
+    parse_skipped_attributes(&String::from_utf8_lossy(&output.stdout))
+}
+
+fn parse_skipped_attributes(output: &str) -> HashMap<String, String> {
+    //! Parses the output of `git check-attr -z`, which consists of a path,
+    //! an attribute and its value, each followed by a NUL byte.
+    //!
+    //! Returns the first attribute that is set for each file. Attributes
+    //! that are unset, unspecified or set to another value are ignored.
+    let mut attributes = HashMap::new();
+    let fields: Vec<&str> = output.split('\0').collect();
+    for entry in fields.chunks_exact(3) {
+        let (file, attribute, value) = (entry[0], entry[1], entry[2]);
+        if value == "set" || value == "true" {
+            attributes
+                .entry(file.to_string())
+                .or_insert_with(|| attribute.to_string());
+        }
+    }
+
+    attributes
+}
+
+pub fn is_valid_revision(revision: &str) -> bool {
+    //! Executes the command `git rev-parse --verify {revision}^{tree}`.
+    //! Returns whether the revision names a commit or tree.
+    Command::new("git")
         .arg("rev-parse")
-        .arg("--abbrev-ref")
-        .arg("HEAD")
+        .arg("--quiet")
+        .arg("--verify")
+        .arg(format!("{}^{{tree}}", revision))
         .output()
-        .expect("Failed to execute `git rev-parse --abbrev-ref HEAD`");
-    let output = str::from_utf8(&command.stdout).unwrap();
+        .map(|output| output.status.success())
+        .unwrap_or(false)
+}
 
-    output.trim().to_string()
+pub fn get_blame(source: &Source, path: &str) -> HashMap<u32, Blame> {
+    //! Executes the command `git blame --porcelain [{revision}] {path}`.
+    //! Returns the author and commit of each line, see parse_blame.
+    let mut command = Command::new("git");
+    command
+        .arg("-c")
+        .arg("core.quotePath=false")
+        .arg("blame")
+        .arg("--porcelain");
+    if let Source::Revision(revision) = source {
+        command.arg(revision);
+    }
+    let command = command
+        .arg("--")
+        .arg(path)
+        .output()
+        .expect("Failed to execute `git blame --porcelain`");
+
+    parse_blame(&String::from_utf8_lossy(&command.stdout))
+}
+
+fn parse_blame(output: &str) -> HashMap<u32, Blame> {
+    //! Parses the output of `git blame --porcelain` into a map of line
+    //! number to the author and commit that last changed it, leaving out
+    //! uncommitted lines.
+    //!
+    //! Commits are named by SHA-1 or SHA-256 hashes, depending on the
+    //! object format of the repository.
+
+    // Each line of the file is introduced by a header of the form
+    // "<sha> <original line> <final line> [<lines in group>]". Details such
+    // as the author are only listed the first time a commit appears.
+    let mut commit_to_blame: HashMap<&str, Blame> = HashMap::new();
+    let mut line_to_blame = HashMap::new();
+    let mut commit = "";
+    let mut line_number = 0;
+    let mut original_line_number = 0;
+    let mut time = 0;
+    for line in output.lines() {
+        if line.starts_with('\t') {
+            match commit_to_blame.get(commit) {
+                Some(blame) if blame.author != UNCOMMITTED_AUTHOR => {
+                    let blame = Blame {
+                        line_number: original_line_number,
+                        ..blame.clone()
+                    };
+                    line_to_blame.insert(line_number, blame);
+                }
+                _ => {}
+            }
+            continue;
+        }
+
+        let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+        let blame = commit_to_blame.get_mut(commit);
+        match (key, blame) {
+            ("author", Some(blame)) => blame.author = value.to_string(),
+            ("author-mail", Some(blame)) => {
+                let email = value.trim_start_matches('<').trim_end_matches('>');
+                blame.email = email.to_string();
+            }
+            ("author-time", Some(_)) => time = value.parse().unwrap_or(0),
+            ("author-tz", Some(blame)) => blame.date = format_date(time, value),
+            ("filename", Some(blame)) => blame.path = value.to_string(),
+            _ => {
+                let fields: Vec<&str> = line.split(' ').collect();
+                let is_header = fields.len() >= 3
+                    && (fields[0].len() == 40 || fields[0].len() == 64)
+                    && fields[0].chars().all(|c| c.is_ascii_hexdigit());
+                if is_header {
+                    commit = fields[0];
+                    original_line_number = fields[1].parse().unwrap_or(0);
+                    line_number = fields[2].parse().unwrap_or(0);
+                    commit_to_blame.entry(commit).or_insert_with(|| Blame {
+                        commit: commit.to_string(),
+                        ..Blame::default()
+                    });
+                }
+            }
+        }
+    }
+
+    line_to_blame
+}
+
+fn format_date(timestamp: i64, time_zone: &str) -> String {
+    //! Formats a unix timestamp as a date of the form YYYY-MM-DD in the
+    //! given time zone, which is of the form +HHMM or -HHMM.
+    let sign = if time_zone.starts_with('-') { -1 } else { 1 };
+    let digits = time_zone.trim_start_matches(['+', '-']);
+    let offset: i64 = digits.parse().unwrap_or(0);
+    let seconds = timestamp + sign * (offset / 100 * 3600 + offset % 100 * 60);
+
+    // Converts days since 1970-01-01 to a date in the Gregorian calendar,
+    // see http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let days = seconds.div_euclid(86400) + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524
+        - day_of_era / 146_096)
+        / 365;
+    let day_of_year =
+        day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn added_lines_are_parsed_from_every_hunk_of_every_file() {
+        let diff = "\
+diff --git a/src/a.rs b/src/a.rs
+index 1111111..2222222 100644
+--- a/src/a.rs
++++ b/src/a.rs
+@@ -0,0 +1,2 @@
++// TODO: First
++let a = 1;
+@@ -10 +12 @@ fn main() {
+-    old();
++    new();
+@@ -20,3 +22,0 @@
+-removed
+-removed
+-removed
+diff --git a/src/b.c b/src/b.c
+new file mode 100644
+index 0000000..3333333
+--- /dev/null
++++ b/src/b.c
+@@ -0,0 +1,3 @@
++++ c;
++++ b/not a file
++// TODO: Second
+diff --git a/src/gone.rs b/src/gone.rs
+deleted file mode 100644
+--- a/src/gone.rs
++++ /dev/null
+@@ -1 +0,0 @@
+-// TODO: Gone
+";
+        let file_to_lines = parse_added_lines(diff);
+        assert_eq!(file_to_lines.len(), 2);
+        assert_eq!(file_to_lines["src/a.rs"], vec![1..3, 12..13]);
+        assert_eq!(file_to_lines["src/b.c"], vec![1..4]);
+    }
+
+    #[test]
+    fn only_set_attributes_are_parsed() {
+        let output = [
+            "a.min.js\0binary\0unspecified\0",
+            "a.min.js\0linguist-generated\0true\0",
+            "a.min.js\0linguist-vendored\0set\0",
+            "b.rs\0binary\0unset\0",
+            "b.rs\0linguist-generated\0false\0",
+            "b.rs\0linguist-vendored\0unspecified\0",
+            "dir/c d.png\0binary\0set\0",
+            "dir/c d.png\0linguist-generated\0unspecified\0",
+            "dir/c d.png\0linguist-vendored\0other\0",
+        ]
+        .concat();
+        let attributes = parse_skipped_attributes(&output);
+        assert_eq!(attributes.len(), 2);
+        assert_eq!(attributes["a.min.js"], "linguist-generated");
+        assert_eq!(attributes["dir/c d.png"], "binary");
+        assert!(parse_skipped_attributes("").is_empty());
+    }
+
+    #[test]
+    fn blame_is_parsed_for_every_committed_line() {
+        let output = "\
+05b5ca0b986dc8e724d8ef834898456d1d890f70 1 1 1
+author Ann
+author-mail <ann@example.com>
+author-time 978303600
+author-tz +0300
+committer Ann
+committer-mail <ann@example.com>
+committer-time 978307200
+committer-tz +0000
+summary first
+boundary
+filename old.rs
+\ta
+2f57a6a0787ebb59f36746cd4d4e13ed007b088e 2 2 1
+author Bob
+author-mail <bob@example.com>
+author-time 1564381800
+author-tz -0700
+committer Bob
+committer-mail <bob@example.com>
+committer-time 1564381800
+committer-tz -0700
+summary second
+previous 05b5ca0b986dc8e724d8ef834898456d1d890f70 f.rs
+filename f.rs
+\tc
+05b5ca0b986dc8e724d8ef834898456d1d890f70 2 3 1
+\tb
+2f57a6a0787ebb59f36746cd4d4e13ed007b088e 4 4 1
+\td
+0000000000000000000000000000000000000000 5 5 1
+author Not Committed Yet
+author-mail <not.committed.yet>
+author-time 1792304422
+author-tz +0000
+committer Not Committed Yet
+committer-mail <not.committed.yet>
+committer-time 1792304422
+committer-tz +0000
+summary Version of f.rs from f.rs
+previous 2f57a6a0787ebb59f36746cd4d4e13ed007b088e f.rs
+filename f.rs
+\te
+";
+        let line_to_blame = parse_blame(output);
+        let mut lines: Vec<&u32> = line_to_blame.keys().collect();
+        lines.sort();
+        assert_eq!(lines, vec![&1, &2, &3, &4]);
+
+        let first = &line_to_blame[&3];
+        assert_eq!(first.author, "Ann");
+        assert_eq!(first.email, "ann@example.com");
+        assert_eq!(first.commit, "05b5ca0b986dc8e724d8ef834898456d1d890f70");
+        assert_eq!(first.date, "2001-01-01");
+        assert_eq!(first.path, "old.rs");
+        assert_eq!(first.line_number, 2);
+
+        let second = &line_to_blame[&4];
+        assert_eq!(second.author, "Bob");
+        assert_eq!(second.date, "2019-07-28");
+        assert_eq!(second.path, "f.rs");
+        assert_eq!(second.line_number, 4);
+    }
+
+    #[test]
+    fn blame_is_parsed_in_sha256_repositories() {
+        let commit =
+            "d0b2ffef84d4ea56fd701a3122be8569d51dd893f31d0ff4dffe8547faf2cdee";
+        let output = format!(
+            "{} 1 1 1\n\
+             author Bob\n\
+             author-mail <bob@example.com>\n\
+             author-time 1564381800\n\
+             author-tz -0700\n\
+             summary first\n\
+             boundary\n\
+             filename f.rs\n\
+             \ta\n",
+            commit
+        );
+        let line_to_blame = parse_blame(&output);
+        assert_eq!(line_to_blame.len(), 1);
+        assert_eq!(line_to_blame[&1].commit, commit);
+        assert_eq!(line_to_blame[&1].author, "Bob");
+    }
+
+    #[test]
+    fn dates_are_in_the_time_zone_of_the_author() {
+        // 2019-07-29 06:30 UTC
+        assert_eq!(format_date(1_564_381_800, "+0000"), "2019-07-29");
+        assert_eq!(format_date(1_564_381_800, "-0700"), "2019-07-28");
+        assert_eq!(format_date(1_564_381_800, "-0630"), "2019-07-29");
+        assert_eq!(format_date(1_564_381_800, "+1730"), "2019-07-30");
+        // 1970-01-01 00:00 UTC
+        assert_eq!(format_date(0, "-0001"), "1969-12-31");
+        assert_eq!(format_date(0, "+0000"), "1970-01-01");
+        // 1969-07-20 20:17 UTC
+        assert_eq!(format_date(-14_182_980, "+0000"), "1969-07-20");
+        assert_eq!(format_date(-14_182_980, "+0400"), "1969-07-21");
+        // 1900-03-01 00:00 UTC, after a year that is not a leap year.
+        assert_eq!(format_date(-2_203_891_200, "+0000"), "1900-03-01");
+        assert_eq!(format_date(-2_203_891_200, "-0100"), "1900-02-28");
+    }
 }
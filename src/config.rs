@@ -0,0 +1,95 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::Deserialize;
+
+pub const FILE_NAME: &str = ".todo-to-issue.toml";
+
+/// Settings shared by everyone working on a repository, read from a
+/// .todo-to-issue.toml file at its root. Command line flags take precedence
+/// over every value set here.
+#[derive(Default, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    /// Patterns of the files to search, see pattern::Pattern.
+    #[serde(alias = "patterns")]
+    pub include: Option<Vec<String>>,
+    /// Patterns of the files to never search.
+    pub exclude: Option<Vec<String>>,
+    /// Maps marker keywords to the label of their issues.
+    pub markers: Option<BTreeMap<String, String>>,
+    /// Labels added to every issue on top of the marker's label.
+    pub labels: Option<Vec<String>>,
+    /// Users assigned to every issue.
+    pub assignees: Option<Vec<String>>,
+    /// Maps commit author emails to the user assigned to their todos.
+    pub authors: Option<BTreeMap<String, String>>,
+    /// Template of the body of every issue.
+    pub template: Option<String>,
+    /// Template of the title of every issue.
+    pub title_template: Option<String>,
+    /// Maps marker keywords to the templates of their issues, overriding
+    /// the ones above.
+    pub templates: Option<BTreeMap<String, MarkerTemplates>>,
+    /// Name of the git remote to open issues in.
+    pub remote: Option<String>,
+    /// Service hosting the issues, either github or gitlab. Guessed from
+    /// the host of the remote by default.
+    pub tracker: Option<String>,
+    /// Base url of the tracker's API, derived from the host of the remote
+    /// by default.
+    pub api_url: Option<String>,
+    /// Maps file extensions or names to their comment delimiters.
+    pub syntax: Option<BTreeMap<String, Vec<String>>>,
+    /// Where the files to search are read from, either index or worktree.
+    pub source: Option<String>,
+    pub ignore_case: Option<bool>,
+    pub write_back: Option<bool>,
+}
+
+/// Title and body templates of the issues of a marker.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MarkerTemplates {
+    pub title: Option<String>,
+    pub body: Option<String>,
+}
+
+impl Config {
+    pub fn load(path: &Path, is_required: bool) -> Result<Config, String> {
+        //! Reads and validates the config file at the given path.
+        //!
+        //! Returns the default (empty) config if the file does not exist and
+        //! is not required, which is only the case for the default file, or
+        //! an error message describing why it is invalid.
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(ref e)
+                if e.kind() == io::ErrorKind::NotFound && !is_required =>
+            {
+                return Ok(Config::default());
+            }
+            Err(e) => {
+                return Err(format!("Unable to read {}: {}", path.display(), e))
+            }
+        };
+
+        toml::from_str(&contents)
+            .map_err(|e| format!("Invalid config in {}: {}", path.display(), e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_file_is_only_an_error_if_required() {
+        let path = Path::new("does-not-exist.toml");
+        assert!(Config::load(path, false).is_ok());
+        let error = Config::load(path, true).err().unwrap();
+        assert!(error.starts_with("Unable to read does-not-exist.toml"));
+    }
+}
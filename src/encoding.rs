@@ -0,0 +1,149 @@
+use std::borrow::Cow;
+use std::char;
+
+const UTF8_BOM: &[u8] = &[0xef, 0xbb, 0xbf];
+const UTF16_LE_BOM: &[u8] = &[0xff, 0xfe];
+const UTF16_BE_BOM: &[u8] = &[0xfe, 0xff];
+
+/// Number of bytes at the start of a file checked for a NUL byte to tell
+/// whether it is binary, as git does.
+const BINARY_SNIFF_LENGTH: usize = 8000;
+
+/// How the text of a file is encoded, as told by its byte order mark.
+/// Files without one are assumed to be UTF-8.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Encoding {
+    Utf8,
+    Utf8Bom,
+    Utf16Le,
+    Utf16Be,
+}
+
+impl Encoding {
+    pub fn detect(bytes: &[u8]) -> Encoding {
+        if bytes.starts_with(UTF8_BOM) {
+            Encoding::Utf8Bom
+        } else if bytes.starts_with(UTF16_LE_BOM) {
+            Encoding::Utf16Le
+        } else if bytes.starts_with(UTF16_BE_BOM) {
+            Encoding::Utf16Be
+        } else {
+            Encoding::Utf8
+        }
+    }
+
+    pub fn get_bom_length(self) -> usize {
+        match self {
+            Encoding::Utf8 => 0,
+            Encoding::Utf8Bom => UTF8_BOM.len(),
+            Encoding::Utf16Le | Encoding::Utf16Be => UTF16_LE_BOM.len(),
+        }
+    }
+
+    pub fn is_utf16(self) -> bool {
+        self == Encoding::Utf16Le || self == Encoding::Utf16Be
+    }
+}
+
+pub fn is_binary(bytes: &[u8]) -> bool {
+    //! Whether the file is binary, i.e. it has a NUL byte near its start.
+    //! UTF-16 text is full of NUL bytes, so it is never considered binary.
+    !Encoding::detect(bytes).is_utf16()
+        && bytes
+            .iter()
+            .take(BINARY_SNIFF_LENGTH)
+            .any(|byte| *byte == 0)
+}
+
+pub fn decode(bytes: &[u8]) -> Cow<'_, str> {
+    //! Decodes the text of a file without its byte order mark, borrowing it
+    //! if it is valid UTF-8.
+    //!
+    //! Invalid sequences, e.g. Latin-1 characters in a UTF-8 file, are
+    //! replaced by U+FFFD so the rest of the file can still be searched.
+    let encoding = Encoding::detect(bytes);
+    let text = &bytes[encoding.get_bom_length()..];
+    match encoding {
+        Encoding::Utf16Le => decode_utf16(text, u16::from_le_bytes).into(),
+        Encoding::Utf16Be => decode_utf16(text, u16::from_be_bytes).into(),
+        _ => String::from_utf8_lossy(text),
+    }
+}
+
+fn decode_utf16(bytes: &[u8], to_unit: fn([u8; 2]) -> u16) -> String {
+    //! Decodes UTF-16 text, replacing unpaired surrogates and a trailing
+    //! odd byte by U+FFFD.
+    let units = bytes.chunks(2).map(|pair| match *pair {
+        [first, second] => to_unit([first, second]),
+        _ => char::REPLACEMENT_CHARACTER as u16,
+    });
+
+    char::decode_utf16(units)
+        .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_utf16(text: &str, to_bytes: fn(u16) -> [u8; 2]) -> Vec<u8> {
+        text.encode_utf16().flat_map(to_bytes).collect()
+    }
+
+    #[test]
+    fn utf8_is_decoded_with_or_without_a_bom() {
+        assert!(Encoding::detect(b"// TODO") == Encoding::Utf8);
+        assert_eq!(decode("// TODO: é".as_bytes()), "// TODO: é");
+        assert!(matches!(decode(b"// TODO"), Cow::Borrowed(_)));
+
+        let bytes = [UTF8_BOM, "// TODO: é".as_bytes()].concat();
+        assert!(Encoding::detect(&bytes) == Encoding::Utf8Bom);
+        assert_eq!(decode(&bytes), "// TODO: é");
+    }
+
+    #[test]
+    fn utf16_is_decoded_in_either_byte_order() {
+        let le =
+            [UTF16_LE_BOM, &to_utf16("// TODO: é", u16::to_le_bytes)].concat();
+        assert!(Encoding::detect(&le) == Encoding::Utf16Le);
+        assert_eq!(decode(&le), "// TODO: é");
+
+        let be =
+            [UTF16_BE_BOM, &to_utf16("// TODO: é", u16::to_be_bytes)].concat();
+        assert!(Encoding::detect(&be) == Encoding::Utf16Be);
+        assert_eq!(decode(&be), "// TODO: é");
+    }
+
+    #[test]
+    fn odd_trailing_utf16_byte_is_replaced() {
+        let mut bytes =
+            [UTF16_LE_BOM, &to_utf16("ab", u16::to_le_bytes)].concat();
+        bytes.push(b'c');
+        assert_eq!(decode(&bytes), "ab\u{fffd}");
+    }
+
+    #[test]
+    fn invalid_utf8_is_replaced() {
+        // "café" in Latin-1
+        assert_eq!(decode(b"// TODO: caf\xe9"), "// TODO: caf\u{fffd}");
+    }
+
+    #[test]
+    fn only_nul_bytes_outside_utf16_are_binary() {
+        assert!(is_binary(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"));
+        assert!(!is_binary(b"// TODO: text"));
+
+        let le =
+            [UTF16_LE_BOM, &to_utf16("// TODO", u16::to_le_bytes)].concat();
+        assert!(!is_binary(&le));
+        let be =
+            [UTF16_BE_BOM, &to_utf16("// TODO", u16::to_be_bytes)].concat();
+        assert!(!is_binary(&be));
+
+        // A NUL byte past the sniffed length is not checked, like git.
+        let mut text = vec![b'a'; BINARY_SNIFF_LENGTH];
+        text.push(0);
+        assert!(!is_binary(&text));
+    }
+}
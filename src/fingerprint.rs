@@ -0,0 +1,123 @@
+const PREFIX: &str = "<!-- todo-to-issue:fingerprint=";
+const SUFFIX: &str = " -->";
+/// Separates the fingerprint from the file of its todo in the comment.
+const FILE_PREFIX: &str = " file=";
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+pub fn compute(
+    file: &str,
+    title: &str,
+    context: &str,
+    occurrence: usize,
+) -> String {
+    //! Identifies a todo by its title and the file it is in, relative to
+    //! the root of the repository, ignoring case and whitespace, so it stays
+    //! the same when the code around it changes or its issue is edited.
+    //!
+    //! Todos with the same title in a file are told apart by some context,
+    //! e.g. the code following them, which is left out if empty, and then by
+    //! the number of the occurrence, starting at 0.
+    //! Returns the hash of these (see fingerprint::hash).
+    let mut text = format!("{}\0{}", file.replace('\\', "/"), normalize(title));
+    if !context.trim().is_empty() {
+        text += &format!("\0{}", normalize(context));
+    }
+    if occurrence > 0 {
+        text += &format!("\0{}", occurrence);
+    }
+
+    hash(text.as_bytes())
+}
+
+pub fn hash(bytes: &[u8]) -> String {
+    //! Returns the 64 bit FNV-1a hash of the bytes as 16 hex digits.
+    let mut hash = FNV_OFFSET_BASIS;
+    for byte in bytes {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+
+    format!("{:016x}", hash)
+}
+
+pub fn to_comment(fingerprint: &str, file: Option<&str>) -> String {
+    //! Returns the fingerprint, along with the file of its todo if known, as
+    //! an HTML comment, which is hidden when the body of an issue is
+    //! rendered.
+    match file {
+        Some(file) => {
+            format!(
+                "{}{}{}{}{}",
+                PREFIX, fingerprint, FILE_PREFIX, file, SUFFIX
+            )
+        }
+        None => format!("{}{}{}", PREFIX, fingerprint, SUFFIX),
+    }
+}
+
+pub fn find(body: &str) -> Option<String> {
+    //! Parses the fingerprint written in the body of an issue by
+    //! fingerprint::to_comment, if there is one.
+    let comment = find_comment(body)?;
+    let fingerprint = match comment.find(FILE_PREFIX) {
+        Some(end) => comment[..end].trim(),
+        None => comment.trim(),
+    };
+    if fingerprint.is_empty() {
+        return None;
+    }
+
+    Some(fingerprint.to_string())
+}
+
+pub fn find_file(body: &str) -> Option<String> {
+    //! Parses the file of the todo written in the body of an issue by
+    //! fingerprint::to_comment, if there is one.
+    let comment = find_comment(body)?;
+    let start = comment.find(FILE_PREFIX)? + FILE_PREFIX.len();
+    let file = comment[start..].trim();
+    if file.is_empty() {
+        return None;
+    }
+
+    Some(file.to_string())
+}
+
+fn find_comment(body: &str) -> Option<&str> {
+    //! Returns the text of the comment written by fingerprint::to_comment.
+    let start = body.find(PREFIX)? + PREFIX.len();
+    let length = body[start..].find(SUFFIX)?;
+
+    Some(&body[start..start + length])
+}
+
+fn normalize(text: &str) -> String {
+    //! Lowercases the text and collapses all whitespace into single spaces.
+    text.split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fingerprint_and_file_are_found_in_the_body() {
+        let comment = to_comment("89ded72f40d49f8e", Some("src/my file.rs"));
+        let body = format!("Some text\n\n{}", comment);
+        assert_eq!(find(&body), Some("89ded72f40d49f8e".to_string()));
+        assert_eq!(find_file(&body), Some("src/my file.rs".to_string()));
+    }
+
+    #[test]
+    fn file_is_optional() {
+        let body = to_comment("89ded72f40d49f8e", None);
+        assert_eq!(find(&body), Some("89ded72f40d49f8e".to_string()));
+        assert_eq!(find_file(&body), None);
+        assert_eq!(find("No comment"), None);
+    }
+}
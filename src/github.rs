@@ -0,0 +1,148 @@
+use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION};
+use reqwest::{Client, Method, Url};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use super::fingerprint;
+use super::issue;
+use super::remote;
+use super::request;
+use super::tracker;
+
+use issue::{Issue, Opened};
+use remote::Remote;
+use tracker::Tracker;
+
+const HOST: &str = "github.com";
+const API_ENDPOINT: &str = "https://api.github.com";
+
+pub struct GitHub {
+    client: Client,
+    url: String,
+    remote_url: String,
+    headers: HeaderMap,
+}
+
+#[derive(Debug, Deserialize)]
+struct Response {
+    title: String,
+    number: usize,
+    body: Option<String>,
+    state: String,
+    /// Set if the issue is a pull request, which are listed as issues.
+    pull_request: Option<Value>,
+}
+
+impl GitHub {
+    pub fn new(
+        token: String,
+        remote: &Remote,
+        api_url: Option<&str>,
+    ) -> GitHub {
+        //! Creates a new tracker that encapsulates the http client,
+        //! url formatted with the API endpoint and user's remote repo,
+        //! and auth header containing the user's token.
+        //!
+        //! The API endpoint defaults to the one of github.com or of the
+        //! GitHub Enterprise Server the remote is hosted on.
+        let api_url = match api_url {
+            Some(url) => url.to_string(),
+            None if remote.host == HOST => API_ENDPOINT.to_string(),
+            None => format!("https://{}/api/v3", remote.host),
+        };
+        let mut headers = HeaderMap::new();
+        if let Ok(value) = HeaderValue::from_str(&format!("token {}", token)) {
+            headers.insert(AUTHORIZATION, value);
+        }
+
+        GitHub {
+            client: Client::new(),
+            url: format!("{}/repos/{}/issues", api_url, remote.get_path())
+                .to_string(),
+            remote_url: remote.get_web_url(),
+            headers,
+        }
+    }
+}
+
+impl Tracker for GitHub {
+    fn open_issue(&self, issue: &Issue) -> Option<usize> {
+        //! Makes a POST request to create a new issue with
+        //! the inputted params (title, body, labels and assignees).
+        let json = json!({
+            "title": issue.get_title(),
+            "body": issue.get_body(),
+            "labels": issue.get_labels(),
+            "assignees": issue.get_assignees(),
+        });
+        let response: Response = request::send(
+            &self.client,
+            Method::POST,
+            &self.url,
+            &self.headers,
+            &json,
+        )?;
+
+        Some(response.number)
+    }
+
+    fn comment_on_issue(&self, number: usize, comment: &str) -> bool {
+        let url = format!("{}/{}/comments", self.url, number);
+        let json = json!({ "body": comment });
+        request::send::<Value>(
+            &self.client,
+            Method::POST,
+            &url,
+            &self.headers,
+            &json,
+        )
+        .is_some()
+    }
+
+    fn set_issue_state(&self, number: usize, is_open: bool) -> bool {
+        let url = format!("{}/{}", self.url, number);
+        let state = if is_open { "open" } else { "closed" };
+        let json = json!({ "state": state });
+        request::send::<Value>(
+            &self.client,
+            Method::PATCH,
+            &url,
+            &self.headers,
+            &json,
+        )
+        .is_some()
+    }
+
+    fn get_issues_with_label(&self, label: &str) -> Option<Vec<Opened>> {
+        let params = &[
+            ("labels", label),
+            ("state", "all"),
+            ("per_page", request::PER_PAGE),
+        ];
+        let first_page = Url::parse_with_params(&self.url, params)
+            .expect("Failed to build issues url");
+        let responses: Vec<Response> =
+            request::get_all_pages(&self.client, first_page, &self.headers)?;
+
+        let issues = responses
+            .into_iter()
+            .filter(|response| response.pull_request.is_none())
+            .map(|response| Opened {
+                fingerprint: response
+                    .body
+                    .as_deref()
+                    .and_then(fingerprint::find),
+                file: response.body.as_deref().and_then(fingerprint::find_file),
+                title: response.title,
+                number: response.number,
+                is_open: response.state == "open",
+            })
+            .collect();
+
+        Some(issues)
+    }
+
+    fn get_web_url(&self) -> String {
+        self.remote_url.clone()
+    }
+}
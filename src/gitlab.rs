@@ -0,0 +1,147 @@
+use reqwest::header::{HeaderMap, HeaderValue};
+use reqwest::{Client, Method, Url};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use super::fingerprint;
+use super::issue;
+use super::remote;
+use super::request;
+use super::tracker;
+
+use issue::{Issue, Opened};
+use remote::Remote;
+use tracker::Tracker;
+
+const TOKEN_HEADER: &str = "PRIVATE-TOKEN";
+
+pub struct GitLab {
+    client: Client,
+    url: String,
+    remote_url: String,
+    headers: HeaderMap,
+}
+
+#[derive(Debug, Deserialize)]
+struct Response {
+    title: String,
+    /// Number of the issue within the project, as opposed to the id
+    /// which is unique across the whole instance.
+    iid: usize,
+    description: Option<String>,
+    /// Either opened or closed.
+    state: String,
+}
+
+impl GitLab {
+    pub fn new(
+        token: String,
+        remote: &Remote,
+        api_url: Option<&str>,
+    ) -> GitLab {
+        //! Creates a new tracker for the project at the remote's path
+        //! authenticated with the user's token. The API endpoint defaults
+        //! to the one of the host the remote is on.
+        //!
+        //! The project is identified by its url encoded path
+        //! e.g. group%2Fsubgroup%2Fproject
+        let mut headers = HeaderMap::new();
+        if let Ok(value) = HeaderValue::from_str(&token) {
+            headers.insert(TOKEN_HEADER, value);
+        }
+        let project = remote.get_path().replace('/', "%2F");
+        let api_url = match api_url {
+            Some(url) => url.to_string(),
+            None => format!("https://{}/api/v4", remote.host),
+        };
+
+        GitLab {
+            client: Client::new(),
+            url: format!("{}/projects/{}/issues", api_url, project),
+            remote_url: remote.get_web_url(),
+            headers,
+        }
+    }
+}
+
+impl Tracker for GitLab {
+    fn open_issue(&self, issue: &Issue) -> Option<usize> {
+        //! Makes a POST request to create a new issue with the title,
+        //! description and labels as a comma separated list.
+        //!
+        //! Assignees are left out since GitLab only accepts user ids.
+        let json = json!({
+            "title": issue.get_title(),
+            "description": issue.get_body(),
+            "labels": issue.get_labels().join(","),
+        });
+        let response: Response = request::send(
+            &self.client,
+            Method::POST,
+            &self.url,
+            &self.headers,
+            &json,
+        )?;
+
+        Some(response.iid)
+    }
+
+    fn comment_on_issue(&self, number: usize, comment: &str) -> bool {
+        let url = format!("{}/{}/notes", self.url, number);
+        let json = json!({ "body": comment });
+        request::send::<Value>(
+            &self.client,
+            Method::POST,
+            &url,
+            &self.headers,
+            &json,
+        )
+        .is_some()
+    }
+
+    fn set_issue_state(&self, number: usize, is_open: bool) -> bool {
+        let url = format!("{}/{}", self.url, number);
+        let event = if is_open { "reopen" } else { "close" };
+        let json = json!({ "state_event": event });
+        request::send::<Value>(
+            &self.client,
+            Method::PUT,
+            &url,
+            &self.headers,
+            &json,
+        )
+        .is_some()
+    }
+
+    fn get_issues_with_label(&self, label: &str) -> Option<Vec<Opened>> {
+        // Issues in every state are returned unless one is passed.
+        let params = &[("labels", label), ("per_page", request::PER_PAGE)];
+        let first_page = Url::parse_with_params(&self.url, params)
+            .expect("Failed to build issues url");
+        let responses: Vec<Response> =
+            request::get_all_pages(&self.client, first_page, &self.headers)?;
+
+        let issues = responses
+            .into_iter()
+            .map(|response| Opened {
+                fingerprint: response
+                    .description
+                    .as_deref()
+                    .and_then(fingerprint::find),
+                file: response
+                    .description
+                    .as_deref()
+                    .and_then(fingerprint::find_file),
+                title: response.title,
+                number: response.iid,
+                is_open: response.state == "opened",
+            })
+            .collect();
+
+        Some(issues)
+    }
+
+    fn get_web_url(&self) -> String {
+        self.remote_url.clone()
+    }
+}
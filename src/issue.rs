@@ -1,25 +1,77 @@
+use std::fmt;
+
 use console::style;
-use serde::Deserialize;
-use serde_json::{json, value::Value};
+use serde::{Deserialize, Serialize};
+
+use super::fingerprint;
 
-pub const LABEL: &str = "TODO";
 const TITLE_PREFIX: &str = "Title:";
 const BODY_PREFIX: &str = "Body:";
 
+#[derive(Clone)]
 pub struct Issue {
     title: String,
     body: String,
+    labels: Vec<String>,
+    assignees: Vec<String>,
+    location: Option<Location>,
+    fingerprint: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
-pub struct Response {
-    title: String,
-    number: usize,
+/// An issue previously opened in the remote repository.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Opened {
+    pub title: String,
+    pub number: usize,
+    /// Fingerprint of the todo the issue was opened for, if its body
+    /// contains one (see fingerprint::to_comment).
+    pub fingerprint: Option<String>,
+    /// File of the todo the issue was opened for, relative to the root of
+    /// the repository, if its body contains it.
+    pub file: Option<String>,
+    pub is_open: bool,
+}
+
+/// Where the comment an issue was created from appears in the source.
+#[derive(Clone)]
+pub struct Location {
+    pub file: String,
+    pub line_number: u32,
+    /// Byte offset right after the marker keyword in the line.
+    pub column: usize,
+    pub keyword: String,
+    /// Text of the comment after the marker, as it appears in the source.
+    pub text: String,
 }
 
 impl Issue {
-    pub fn new(title: String, body: String) -> Issue {
-        Issue { title, body }
+    pub fn new(title: String, body: String, labels: Vec<String>) -> Issue {
+        Issue {
+            title,
+            body,
+            labels,
+            assignees: Vec::new(),
+            location: None,
+            fingerprint: None,
+        }
+    }
+
+    pub fn with_assignees(self, assignees: Vec<String>) -> Issue {
+        Issue { assignees, ..self }
+    }
+
+    pub fn with_location(self, location: Location) -> Issue {
+        Issue {
+            location: Some(location),
+            ..self
+        }
+    }
+
+    pub fn with_fingerprint(self, fingerprint: String) -> Issue {
+        Issue {
+            fingerprint: Some(fingerprint),
+            ..self
+        }
     }
 
     pub fn from_string(string: String) -> Option<Issue> {
@@ -27,7 +79,7 @@ impl Issue {
         //!
         //! 1. The first line starts with "Title:" followed by a nonempty string
         //! 2. The second line starts with "Body:" followed by a description
-        //! that spans one or more lines.
+        //!    that spans one or more lines.
 
         // 1. Split the string based on \n
         let split: Vec<&str> = string.split("\n").collect();
@@ -57,24 +109,66 @@ impl Issue {
         }
 
         // 7. Construct body from the rest of the file
-        for index in 2..split.len() {
+        for line in &split[2..] {
             body += "\n";
-            body += split[index];
+            body += line;
         }
 
-        Some(Issue { title, body })
+        Some(Issue {
+            title,
+            body,
+            labels: Vec::new(),
+            assignees: Vec::new(),
+            location: None,
+            fingerprint: None,
+        })
+    }
+
+    pub fn edit(&self, string: String) -> Option<Issue> {
+        //! Returns a copy of the issue with the title and body replaced by
+        //! the ones parsed from the string (see Issue::from_string).
+        //! The labels, assignees, location and fingerprint are kept as is.
+        let edited = Issue::from_string(string)?;
+
+        Some(Issue {
+            title: edited.title,
+            body: edited.body,
+            ..self.clone()
+        })
     }
 
     pub fn get_title(&self) -> String {
         self.title.clone()
     }
 
-    pub fn to_string(&self) -> String {
-        format!(
-            "{} {}\n{} {}",
-            TITLE_PREFIX, &self.title, BODY_PREFIX, &self.body
-        )
-        .to_string()
+    pub fn get_body(&self) -> String {
+        //! Returns the body to open the issue with, which ends with the
+        //! fingerprint and file of its todo hidden in an HTML comment.
+        let file = self.location.as_ref().map(|l| l.file.as_str());
+        match self.fingerprint {
+            Some(ref fingerprint) => format!(
+                "{}\n\n{}",
+                self.body,
+                fingerprint::to_comment(fingerprint, file)
+            ),
+            None => self.body.clone(),
+        }
+    }
+
+    pub fn get_labels(&self) -> &[String] {
+        &self.labels
+    }
+
+    pub fn get_assignees(&self) -> &[String] {
+        &self.assignees
+    }
+
+    pub fn get_location(&self) -> Option<&Location> {
+        self.location.as_ref()
+    }
+
+    pub fn get_fingerprint(&self) -> Option<&str> {
+        self.fingerprint.as_deref()
     }
 
     pub fn to_formatted_string(&self) -> String {
@@ -87,24 +181,14 @@ impl Issue {
         )
         .to_string()
     }
-
-    pub fn to_json(&self) -> Value {
-        json!({
-            "title": &self.title,
-            "body": &self.body,
-            "labels": [
-                LABEL,
-            ]
-        })
-    }
 }
 
-impl Response {
-    pub fn get_title(&self) -> String {
-        self.title.clone()
-    }
-
-    pub fn get_number(&self) -> usize {
-        self.number
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {}\n{} {}",
+            TITLE_PREFIX, &self.title, BODY_PREFIX, &self.body
+        )
     }
 }
@@ -1,36 +1,109 @@
 mod cli;
 mod command;
+mod config;
+mod encoding;
+mod fingerprint;
+mod github;
+mod gitlab;
 mod issue;
+mod marker;
 mod parse;
+mod pattern;
+mod remote;
+mod report;
 mod request;
+mod state;
+mod syntax;
+mod template;
+mod tracker;
 
-use request::Request;
+use std::process;
 
 fn main() {
+    process::exit(run());
+}
+
+fn run() -> i32 {
+    //! Runs the program and returns its exit code (see cli::EXIT_SUCCESS).
     let args = match cli::init() {
         Some(args) => args,
-        None => return,
+        None => return cli::EXIT_ERROR,
     };
-    let remote = match command::get_remote_name(args.is_dry_run()) {
-        Some(remote) => remote,
-        None => return,
+    let tracker = tracker::new(&args);
+    let mut state = state::State::load(args.get_scan_key());
+    let issues = match args.is_offline() {
+        true => state.get_issues(&tracker.get_web_url()),
+        false => match tracker.get_issues(&args.get_labels()) {
+            Some(issues) => {
+                state.set_issues(&tracker.get_web_url(), &issues);
+                issues
+            }
+            None => return cli::EXIT_ERROR,
+        },
     };
+    if args.is_offline() && !issues.is_empty() {
+        cli::print_info(&format!(
+            "Using the {} cached by the last run.",
+            cli::handle_plural(&issues.len(), "issue")
+        ));
+    }
 
-    let request = Request::new(args.get_token(), remote);
-    let issues = match request.get_issues(args.is_dry_run()) {
-        Some(issues) => issues,
-        None => return,
-    };
+    let files = command::get_files(args.get_source());
+    let file_to_todos =
+        parse::find_all_todos(&files, &issues, &mut state, &args);
+    state.save();
 
-    let files = command::get_tracked_files();
-    let file_to_issues = parse::find_all_todos(
-        &files,
-        &issues,
-        args.get_pattern(),
-        args.is_verbose(),
-    );
+    if let Some(format) = args.get_format() {
+        print!("{}", report::create_report(format, &file_to_todos, &args));
+        return cli::EXIT_SUCCESS;
+    }
+    if args.is_dry_run() {
+        return cli::EXIT_SUCCESS;
+    }
 
-    if file_to_issues.len() > 0 && !args.is_dry_run() {
-        cli::output_issues_and_prompt_user(&request, &file_to_issues);
+    let mut summary = cli::Summary::new(parse::count_ignored(&file_to_todos));
+    if args.is_sync() {
+        let removed =
+            parse::get_removed_issues(&issues, &files, &file_to_todos);
+        cli::close_removed_issues(
+            tracker.as_ref(),
+            &removed,
+            &mut summary,
+            &mut state,
+            &args,
+        );
     }
+
+    let closed = parse::get_closed_todos(&file_to_todos);
+    if let Some(exit_code) = cli::handle_closed_issues(
+        tracker.as_ref(),
+        &closed,
+        &mut summary,
+        &mut state,
+        &args,
+    ) {
+        state.save();
+        return exit_code;
+    }
+
+    let file_to_issues = parse::get_new_issues(&file_to_todos, &args);
+    let exit_code = match args.is_non_interactive() {
+        true => cli::open_issues_without_prompt(
+            tracker.as_ref(),
+            &file_to_issues,
+            summary,
+            &mut state,
+            &args,
+        ),
+        false if file_to_issues.is_empty() => cli::EXIT_SUCCESS,
+        false => cli::output_issues_and_prompt_user(
+            tracker.as_ref(),
+            &file_to_issues,
+            &mut state,
+            &args,
+        ),
+    };
+    state.save();
+
+    exit_code
 }
@@ -0,0 +1,103 @@
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_KEYWORD: &str = "TODO";
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Marker {
+    keyword: String,
+    label: String,
+}
+
+impl Marker {
+    pub fn new(keyword: &str, label: &str) -> Marker {
+        Marker {
+            keyword: keyword.to_string(),
+            label: label.to_string(),
+        }
+    }
+
+    pub fn from_string(string: &str) -> Result<Marker, String> {
+        //! Parses a marker of the form KEYWORD or KEYWORD=LABEL.
+        //! The label defaults to the keyword itself.
+        let split: Vec<&str> = string.splitn(2, '=').collect();
+        let keyword = split[0].trim();
+        let label = split.get(1).map(|l| l.trim()).unwrap_or(keyword);
+
+        let is_word = keyword.chars().all(|c| c.is_alphanumeric() || c == '_');
+        if keyword.is_empty() || !is_word {
+            return Err(format!(
+                "Invalid marker \"{}\". Keywords may only contain letters, \
+                 digits and underscores.",
+                string
+            ));
+        }
+        if label.is_empty() {
+            return Err(format!("Missing label for marker \"{}\".", keyword));
+        }
+
+        Ok(Marker::new(keyword, label))
+    }
+
+    pub fn get_keyword(&self) -> &str {
+        &self.keyword
+    }
+
+    pub fn get_label(&self) -> &str {
+        &self.label
+    }
+
+    pub fn find(&self, text: &str, ignore_case: bool) -> Option<usize> {
+        //! Returns the byte index of the first occurrence of the keyword in
+        //! the text that is not part of a larger word, e.g. "TODO" matches
+        //! "TODO:" and "TODO(foo)" but not "TODOS" or "MASTODON".
+        let haystack = match ignore_case {
+            true => text.to_ascii_lowercase(),
+            false => text.to_string(),
+        };
+        let needle = match ignore_case {
+            true => self.keyword.to_ascii_lowercase(),
+            false => self.keyword.clone(),
+        };
+
+        let is_word_char = |c: char| c.is_alphanumeric() || c == '_';
+        let mut start = 0;
+        while let Some(found) = haystack[start..].find(&needle) {
+            let index = start + found;
+            let end = index + needle.len();
+            let before = haystack[..index].chars().next_back();
+            let after = haystack[end..].chars().next();
+            if !before.is_some_and(is_word_char)
+                && !after.is_some_and(is_word_char)
+            {
+                return Some(index);
+            }
+            start = end;
+        }
+
+        None
+    }
+}
+
+pub fn get_labels(markers: &[Marker]) -> Vec<String> {
+    //! Returns the distinct labels used by the markers, in order.
+    let mut labels: Vec<String> = Vec::new();
+    for marker in markers {
+        if !labels.iter().any(|label| label == marker.get_label()) {
+            labels.push(marker.get_label().to_string());
+        }
+    }
+
+    labels
+}
+
+pub fn describe(markers: &[Marker]) -> String {
+    //! Returns a human readable list of the marker keywords
+    //! e.g. "TODO, FIXME or HACK".
+    let keywords: Vec<&str> = markers.iter().map(|m| m.get_keyword()).collect();
+    match keywords.split_last() {
+        Some((last, rest)) if !rest.is_empty() => {
+            format!("{} or {}", rest.join(", "), last)
+        }
+        _ => keywords.join(""),
+    }
+}
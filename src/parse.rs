@@ -1,155 +1,1322 @@
-use std::collections::{HashMap, HashSet};
-use std::fs::File;
-use std::io::{self, prelude::*, BufReader};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::io;
 use std::str;
 
 use super::cli;
+use super::command;
+use super::encoding;
+use super::fingerprint;
 use super::issue;
+use super::marker;
+use super::state;
+use super::syntax;
+use cli::Args;
+use command::Blame;
 use console::style;
-use issue::Issue;
+use encoding::Encoding;
+use issue::{Issue, Location, Opened};
+use marker::Marker;
+use rayon::prelude::*;
+use rayon::ThreadPoolBuilder;
+use serde::{Deserialize, Serialize};
+use state::State;
 
-const TODO: &str = "TODO";
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Todo {
+    pub file: String,
+    pub marker: Marker,
+    pub line_number: u32,
+    /// Column of the first character of the marker keyword, starting at 1.
+    pub column_number: usize,
+    /// Byte offset right after the marker keyword in the line.
+    offset: usize,
+    pub title: String,
+    pub description: Vec<String>,
+    /// Who last changed the line of the todo, if it has been committed.
+    pub blame: Option<Blame>,
+    /// The line of the todo and the ones surrounding it.
+    snippet: Vec<String>,
+    /// Number of the issue tracking the todo, either referenced in the
+    /// comment as e.g. TODO(#123) or found in the remote repository.
+    pub issue: Option<usize>,
+    pub is_tracked: bool,
+    /// Whether the issue tracking the todo was closed even though the todo
+    /// is still in the code.
+    pub is_closed: bool,
+    /// Whether the todo should never become an issue, because of a
+    /// directive in the code or the user ignoring it in a previous run.
+    pub is_ignored: bool,
+    /// Identifies the todo across runs, even if it moves or its issue is
+    /// edited (see fingerprint::compute).
+    pub fingerprint: String,
+}
+
+/// Previously opened issues, looked up by fingerprint or title.
+struct Previous<'a> {
+    fingerprint_to_number: HashMap<&'a str, usize>,
+    /// Titles of the issues without a fingerprint.
+    title_to_number: HashMap<&'a str, usize>,
+    /// Titles of the issues whose fingerprint none of the todos have, e.g.
+    /// because the file was renamed. Each is matched by one todo at most.
+    orphan_title_to_numbers: HashMap<&'a str, Vec<usize>>,
+    closed: HashSet<usize>,
+}
+
+/// The todos a worker thread found in a file, see scan_file.
+enum Scan {
+    /// The file has not changed since the previous run.
+    Unchanged(Vec<Todo>),
+    /// The file was scanned, along with the hash of its contents.
+    Scanned(Vec<Todo>, String),
+    /// The file was not searched for the given reason.
+    Skipped(String),
+}
+
+/// Number of lines shown before and after a todo in the body of its issue.
+const SNIPPET_CONTEXT: usize = 3;
+
+/// Prefix of the directives written in comments to keep todos from
+/// becoming issues, e.g. "todo-to-issue: ignore-next-line".
+const DIRECTIVE_PREFIX: &str = "todo-to-issue:";
+/// Note after a marker that keeps its todo from becoming an issue,
+/// e.g. TODO(no-issue): title
+const NO_ISSUE_NOTE: &str = "no-issue";
+
+/// A directive written in a comment, see DIRECTIVE_PREFIX.
+enum Directive {
+    /// Ignores the todos on the line after the comment.
+    IgnoreNextLine,
+    /// Ignores every todo in the file.
+    IgnoreFile,
+}
+
+/// Describes which lines following a todo belong to its description.
+enum Continuation {
+    None,
+    /// Line comments starting at the given column.
+    Line(usize),
+    /// The remaining lines of an unclosed block comment.
+    Block,
+}
 
 pub fn find_all_todos(
-    files: &Vec<String>,
-    issues: &HashSet<String>,
-    pattern: &glob::Pattern,
-    is_verbose: bool,
-) -> HashMap<String, Vec<Issue>> {
+    files: &[String],
+    issues: &[Opened],
+    state: &mut State,
+    args: &Args,
+) -> BTreeMap<String, Vec<Todo>> {
     //! Reads every file that matches the specified glob pattern
     //! and searches for "todo" comments line by line.
     //!
-    //! Returns a HashMap that maps file path to a vector of Issue objects that
-    //! represents the "todos" found in the file.
-    let mut file_to_issues = HashMap::new();
+    //! A "todo" is any comment containing one of the configured marker
+    //! keywords. Todos that reference an issue or match a previously opened
+    //! one are marked as tracked (see track_todos), and the ones ignored in
+    //! a previous run are marked as ignored.
+    //!
+    //! Returns a BTreeMap that maps the path of every file searched to a
+    //! vector of every Todo found in the file, which may be empty. Only the
+    //! new ones are counted in the output.
+    let mut file_to_todos = BTreeMap::new();
     let mut total = 0;
+    let mut num_closed = 0;
+    let mut num_ignored = 0;
+    state.retain_files(files);
 
-    let pattern_str = pattern.as_str();
-    if pattern_str == "*" {
-        println!("Searching all files tracked by git for TODO comments...");
+    let patterns: Vec<String> = args
+        .get_include()
+        .iter()
+        .map(|pattern| format!("\"{}\"", pattern.as_str()))
+        .collect();
+    let keywords = marker::describe(args.get_markers());
+    if patterns.is_empty() {
+        cli::print_info(&format!(
+            "Searching all files {} for {} comments...",
+            args.get_source().describe(),
+            keywords
+        ));
     } else {
-        println!(
-            "Searching all files matching pattern \"{}\" for TODO comments...",
-            pattern_str
-        );
-    }
-
-    for file in files {
-        if pattern.matches(&file) {
-            let result = find_todos_in_file(&file, &issues, is_verbose);
-            if let Ok(vector) = result {
-                let num_issues = vector.len();
-                if num_issues > 0 {
-                    println!(
-                        "Found {} {} in {}",
-                        style(num_issues).bold(),
-                        cli::handle_plural(&num_issues, "TODO"),
-                        style(file).italic()
-                    );
-                    file_to_issues.insert(file.clone(), vector);
-                    total += num_issues;
+        cli::print_info(&format!(
+            "Searching all files matching {} {} for {} comments...",
+            cli::handle_plural(&patterns.len(), "pattern"),
+            patterns.join(", "),
+            keywords
+        ));
+    }
+    if let Some(base) = args.get_since() {
+        cli::print_info(&format!(
+            "Only including TODOs added since {}.",
+            style(base).italic()
+        ));
+    }
+
+    let mut included: Vec<&String> = files
+        .iter()
+        .filter(|file| args.is_included(file) && args.is_changed(file))
+        .collect();
+    included.sort();
+
+    // Files are scanned in parallel, then handled in order so the output
+    // is the same on every run.
+    let needs_blame = args.get_format().is_some() || !args.is_dry_run();
+    let pool = ThreadPoolBuilder::new()
+        .num_threads(args.get_jobs())
+        .build()
+        .expect("Failed to create the thread pool");
+    let attributes = command::get_skipped_attributes(&included);
+    let cached: &State = state;
+    let scans: Vec<(&String, io::Result<Scan>)> = pool.install(|| {
+        included
+            .par_iter()
+            .map(|file| {
+                let scan = match attributes.get(file.as_str()) {
+                    Some(attribute) => Ok(Scan::Skipped(format!(
+                        "marked as {} in .gitattributes",
+                        attribute
+                    ))),
+                    None => scan_file(file, cached, needs_blame, args),
+                };
+                (*file, scan)
+            })
+            .collect()
+    });
+
+    let fingerprints: HashSet<String> = scans
+        .iter()
+        .filter_map(|(_, scan)| match scan {
+            Ok(Scan::Unchanged(todos)) | Ok(Scan::Scanned(todos, _)) => {
+                Some(todos)
+            }
+            _ => None,
+        })
+        .flatten()
+        .map(|todo| todo.fingerprint.clone())
+        .collect();
+    let mut previous = Previous::new(issues, &fingerprints);
+
+    let is_verbose = args.is_verbose();
+    let mut num_skipped = 0;
+    for (file, scan) in scans {
+        let mut todos = match scan {
+            Ok(Scan::Unchanged(todos)) => {
+                if is_verbose {
+                    cli::print_dim(&format!(
+                        "Using the cached TODOs of {}",
+                        file
+                    ));
+                }
+                todos
+            }
+            Ok(Scan::Scanned(todos, hash)) => {
+                if is_verbose {
+                    cli::print_dim(&format!("Searching {}", file));
+                    for todo in &todos {
+                        cli::print_info(&format!(
+                            "Line {}: \"{}\"",
+                            todo.line_number, todo.title
+                        ));
+                    }
+                }
+                state.set_todos(file, hash, needs_blame, &todos);
+                todos
+            }
+            Ok(Scan::Skipped(reason)) => {
+                if is_verbose {
+                    cli::print_dim(&format!("Skipping {}: {}", file, reason));
+                }
+                num_skipped += 1;
+                continue;
+            }
+            Err(error) => {
+                if is_verbose {
+                    cli::print_dim(&format!(
+                        "Skipping {}: unable to read it ({})",
+                        file, error
+                    ));
                 }
+                num_skipped += 1;
+                continue;
+            }
+        };
+
+        todos.retain(|todo| args.is_added(file, todo.line_number));
+        track_todos(&mut todos, &mut previous, args);
+        for todo in &todos {
+            if let Some(number) = todo.issue {
+                state.set_comment(number, &todo.title);
             }
         }
+        for todo in &mut todos {
+            todo.is_ignored |= state.is_ignored(&todo.fingerprint);
+        }
+        num_ignored += todos
+            .iter()
+            .filter(|t| t.is_ignored && !t.is_tracked)
+            .count();
+        let num_issues = todos.iter().filter(|t| t.is_new()).count();
+        if num_issues > 0 {
+            cli::print_info(&format!(
+                "Found {} {} in {}",
+                style(num_issues).bold(),
+                cli::handle_plural(&num_issues, "TODO"),
+                style(file).italic()
+            ));
+            total += num_issues;
+        }
+        num_closed += todos.iter().filter(|t| t.is_closed).count();
+        file_to_todos.insert(file.clone(), todos);
+    }
+
+    if num_skipped > 0 && !is_verbose {
+        cli::print_dim(&format!(
+            "Skipped {} {} (binary, generated, vendored or unreadable). \
+             Pass --verbose to list them.",
+            num_skipped,
+            cli::handle_plural(&num_skipped, "file")
+        ));
     }
 
     match total {
         0 => cli::print_success("No TODOs found. You're all set!"),
-        num_issues => println!(
+        num_issues => cli::print_info(&format!(
             "Found {} {} total.",
             style(num_issues).bold(),
             cli::handle_plural(&num_issues, "TODO")
-        ),
+        )),
+    }
+
+    if num_ignored > 0 {
+        cli::print_info(&format!(
+            "Ignored {} {}.",
+            style(num_ignored).bold(),
+            cli::handle_plural(&num_ignored, "TODO")
+        ));
+    }
+
+    if num_closed > 0 {
+        cli::print_warning(&format!(
+            "{} {} still in the code although {} issue was closed.",
+            num_closed,
+            match num_closed {
+                1 => "TODO is",
+                _ => "TODOs are",
+            },
+            match num_closed {
+                1 => "its",
+                _ => "their",
+            }
+        ));
+    }
+
+    file_to_todos
+}
+
+pub fn get_closed_todos(
+    file_to_todos: &BTreeMap<String, Vec<Todo>>,
+) -> Vec<&Todo> {
+    //! Returns the todos whose issue was closed although they are still in
+    //! the code, sorted by file and line.
+    let mut todos: Vec<&Todo> = file_to_todos
+        .values()
+        .flatten()
+        .filter(|todo| todo.is_closed)
+        .collect();
+    todos.sort_by(|a, b| {
+        (&a.file, a.line_number).cmp(&(&b.file, b.line_number))
+    });
+
+    todos
+}
+
+pub fn count_ignored(file_to_todos: &BTreeMap<String, Vec<Todo>>) -> usize {
+    //! Returns the number of untracked todos that are ignored.
+    file_to_todos
+        .values()
+        .flatten()
+        .filter(|todo| todo.is_ignored && !todo.is_tracked)
+        .count()
+}
+
+pub fn get_new_issues(
+    file_to_todos: &BTreeMap<String, Vec<Todo>>,
+    args: &Args,
+) -> BTreeMap<String, Vec<Issue>> {
+    //! Creates an Issue for every todo that is not tracked yet.
+    //!
+    //! Returns a BTreeMap that maps file path to a vector of Issue objects,
+    //! leaving out files without any new todos.
+    let mut file_to_issues = BTreeMap::new();
+    for (file, todos) in file_to_todos {
+        let issues: Vec<Issue> = todos
+            .iter()
+            .filter(|todo| todo.is_new())
+            .map(|todo| todo.to_issue(args))
+            .collect();
+        if !issues.is_empty() {
+            file_to_issues.insert(file.clone(), issues);
+        }
     }
 
     file_to_issues
 }
 
+pub fn get_removed_issues<'a>(
+    issues: &'a [Opened],
+    files: &[String],
+    file_to_todos: &BTreeMap<String, Vec<Todo>>,
+) -> Vec<&'a Opened> {
+    //! Returns the open issues that none of the todos are tracked by,
+    //! meaning their todo has been removed from the code.
+    //!
+    //! Only issues that name the file of their todo are returned, so the
+    //! ones written by hand never are. The file must have been searched,
+    //! or no longer exist, since a file that was left out or skipped, e.g.
+    //! because it could not be read, may still contain the todo.
+    let numbers: HashSet<usize> = file_to_todos
+        .values()
+        .flatten()
+        .filter_map(|todo| todo.issue)
+        .collect();
+    let files: HashSet<&str> = files.iter().map(|f| f.as_str()).collect();
+
+    issues
+        .iter()
+        .filter(|issue| issue.is_open && !numbers.contains(&issue.number))
+        .filter(|issue| match issue.file {
+            Some(ref file) => {
+                file_to_todos.contains_key(file) || !files.contains(&**file)
+            }
+            None => false,
+        })
+        .collect()
+}
+
+fn add_blame(path: &str, todos: &mut Vec<Todo>, args: &Args) {
+    //! Fills in the author and commit of every todo based on `git blame`.
+    let line_to_blame = command::get_blame(args.get_source(), path);
+    for todo in todos {
+        todo.blame = line_to_blame.get(&todo.line_number).cloned();
+    }
+}
+
+fn scan_file(
+    path: &str,
+    state: &State,
+    needs_blame: bool,
+    args: &Args,
+) -> io::Result<Scan> {
+    //! Reads a file and returns the todos in it, reusing the ones found by
+    //! a previous run if the file has not changed since
+    //! (see State::get_todos).
+    //!
+    //! Runs on the thread pool, so nothing is printed here.
+    let contents = command::read_file(args.get_source(), path)?;
+    if encoding::is_binary(&contents) {
+        return Ok(Scan::Skipped("it contains binary data".to_string()));
+    }
+    let hash = fingerprint::hash(&contents);
+    if let Some(todos) = state.get_todos(path, &hash, needs_blame) {
+        return Ok(Scan::Unchanged(todos));
+    }
+
+    let todos = find_todos_in_file(path, &contents, needs_blame, args);
+
+    Ok(Scan::Scanned(todos, hash))
+}
+
 fn find_todos_in_file(
     path: &str,
-    prev_issues: &HashSet<String>,
-    is_verbose: bool,
-) -> io::Result<(Vec<Issue>)> {
-    //! Reads every line in a file for a "todo" comment, creating an Issue
+    contents: &[u8],
+    needs_blame: bool,
+    args: &Args,
+) -> Vec<Todo> {
+    //! Reads every line in a file for a "todo" comment, creating a Todo
     //! object for each one with the parsed title and description.
     //!
-    //! Returns an IO result containing a vector of Issues if successful.
-    let file = File::open(path)?;
-    let buffer = BufReader::new(file);
-    let mut issues_in_file = Vec::new();
+    //! Comments are detected using the syntax registered for the file's
+    //! extension or name. Comment lines that directly follow a todo at the
+    //! same indentation (or the rest of its block comment) are used as the
+    //! description.
+    //!
+    //! The file is decoded first (see encoding::decode), so files in
+    //! UTF-16 or with invalid UTF-8 are searched as well.
+    //!
+    //! Every todo gets a fingerprint (see fingerprint::compute) and, if
+    //! needed, the author of its line.
+    let syntax = args.get_syntax().get(path);
+    let mut todos: Vec<Todo> = Vec::new();
 
-    if is_verbose {
-        cli::print_dim(&format!("Searching {}", path).to_string());
-    }
     let mut line_number = 0;
-    for line_result in buffer.lines() {
-        if let Err(e) = line_result {
-            return Err(e);
-        }
+    let mut open_block = None;
+    let mut continuation = Continuation::None;
+    let mut ignored_line = 0;
+    let mut is_file_ignored = false;
+    // Number of the last line of the comment of each todo.
+    let mut comment_ends = Vec::new();
+    // Lines end with either LF or CRLF, which are both stripped.
+    let text = encoding::decode(contents);
+    let lines: Vec<&str> = text.lines().collect();
+    for line in &lines {
         line_number += 1;
 
-        let line = line_result.unwrap();
-        if contains_todo(&line) {
-            let title = extract_title(&line);
-            let body = create_body(&line_number, path);
+        // Shebangs look like Bash comments but never contain a todo.
+        if line_number == 1 && line.starts_with("#!") {
+            continue;
+        }
+
+        let was_in_block = open_block.is_some();
+        let comments = syntax.find_comments(line, &mut open_block);
+
+        let mut skip = 0;
+        let text = get_continuation(&continuation, line, &comments, args);
+        match (text, todos.last_mut()) {
+            (Some(text), Some(todo)) if was_in_block || !text.is_empty() => {
+                let is_closed = was_in_block && open_block.is_none();
+                if let Some(end) = comment_ends.last_mut() {
+                    *end = line_number;
+                }
+                // A closing line such as " */" adds nothing to the body.
+                if !is_closed || !text.is_empty() {
+                    todo.description.push(text);
+                }
+                skip = 1;
+                if is_closed {
+                    continuation = Continuation::None;
+                }
+            }
+            _ => continuation = Continuation::None,
+        }
+
+        for comment in comments.iter().skip(skip) {
+            match find_directive(comment.text) {
+                Some(Directive::IgnoreNextLine) => {
+                    ignored_line = line_number + 1;
+                    continue;
+                }
+                Some(Directive::IgnoreFile) => {
+                    is_file_ignored = true;
+                    continue;
+                }
+                None => {}
+            }
+            let (marker, end) = match find_marker(comment.text, args) {
+                Some(found) => found,
+                None => continue,
+            };
+            let after_marker = &comment.text[end..];
+            let title = extract_title(after_marker);
+
+            continuation = match (comment.is_block, open_block) {
+                (false, _) => Continuation::Line(comment.column),
+                (true, Some(_)) => Continuation::Block,
+                (true, None) => Continuation::None,
+            };
+            let start = comment.offset + end - marker.get_keyword().len();
+            let reference = get_reference(after_marker);
+            comment_ends.push(line_number);
+            todos.push(Todo {
+                file: path.to_string(),
+                marker,
+                line_number,
+                column_number: line[..start].chars().count() + 1,
+                offset: comment.offset + end,
+                title,
+                description: Vec::new(),
+                blame: None,
+                snippet: Vec::new(),
+                fingerprint: String::new(),
+                issue: reference,
+                is_tracked: reference.is_some(),
+                is_closed: false,
+                is_ignored: line_number == ignored_line
+                    || has_no_issue_note(after_marker),
+            });
+        }
+    }
+
+    if is_file_ignored {
+        for todo in &mut todos {
+            todo.is_ignored = true;
+        }
+    }
+
+    if needs_blame && !todos.is_empty() {
+        add_blame(path, &mut todos, args);
+    }
+
+    // Todos are told apart by their title, so that editing the code below
+    // one keeps its fingerprint. Only todos with the same title in a file
+    // are told apart by the first line of code after their comment, and
+    // then by their occurrence.
+    let mut title_counts: HashMap<String, usize> = HashMap::new();
+    for todo in &todos {
+        let fingerprint = fingerprint::compute(path, &todo.title, "", 0);
+        *title_counts.entry(fingerprint).or_insert(0) += 1;
+    }
+    let mut occurrences: HashMap<String, usize> = HashMap::new();
+    for (todo, comment_end) in todos.iter_mut().zip(comment_ends) {
+        let index = todo.line_number as usize - 1;
+        let start = index.saturating_sub(SNIPPET_CONTEXT);
+        let end = (index + SNIPPET_CONTEXT + 1).min(lines.len());
+        todo.snippet =
+            lines[start..end].iter().map(|l| l.to_string()).collect();
+
+        let fingerprint = fingerprint::compute(path, &todo.title, "", 0);
+        if title_counts[&fingerprint] == 1 {
+            todo.fingerprint = fingerprint;
+            continue;
+        }
+        let context = lines
+            .iter()
+            .skip(comment_end as usize)
+            .find(|line| !line.trim().is_empty())
+            .map_or("", |line| *line);
+        let fingerprint = fingerprint::compute(path, &todo.title, context, 0);
+        let occurrence = occurrences.entry(fingerprint.clone()).or_insert(0);
+        todo.fingerprint = match *occurrence {
+            0 => fingerprint,
+            n => fingerprint::compute(path, &todo.title, context, n),
+        };
+        *occurrence += 1;
+    }
 
+    todos
+}
+
+fn track_todos(todos: &mut [Todo], previous: &mut Previous, args: &Args) {
+    //! Marks the todos that are tracked by an issue, which is the case if
+    //! they reference an issue, or if a previous issue has the same
+    //! fingerprint. Issues opened before fingerprints existed, or whose
+    //! fingerprint no longer matches any todo, are matched by title instead.
+    let is_verbose = args.is_verbose();
+    for todo in todos {
+        if let Some(number) = todo.issue {
+            todo.is_closed = previous.is_closed(number);
             if is_verbose {
-                println!("Line {}: \"{}\"", &line_number, title)
+                cli::print_warning(&format!(
+                    "Line {}: \"{}\" is already tracked by issue #{}.",
+                    todo.line_number, todo.title, number
+                ));
             }
-            if !prev_issues.contains(title.as_str()) {
-                let issue = Issue::new(title, body);
-                issues_in_file.push(issue);
-            } else if is_verbose {
-                cli::print_warning(
-                    "This issue was already opened in the remote repo.",
-                );
+            continue;
+        }
+        if let Some(number) = previous.find(todo, args) {
+            if is_verbose {
+                cli::print_warning(&format!(
+                    "Line {}: \"{}\" was already opened in the remote repo.",
+                    todo.line_number, todo.title
+                ));
             }
+            todo.issue = Some(number);
+            todo.is_tracked = true;
+            todo.is_closed = previous.is_closed(number);
         }
     }
+}
+
+impl<'a> Previous<'a> {
+    fn new(
+        issues: &'a [Opened],
+        fingerprints: &HashSet<String>,
+    ) -> Previous<'a> {
+        //! Indexes the issues, given the fingerprints of every todo found.
+        let mut fingerprint_to_number = HashMap::new();
+        let mut title_to_number = HashMap::new();
+        let mut orphan_title_to_numbers = HashMap::new();
+        let mut closed = HashSet::new();
+        for issue in issues {
+            if !issue.is_open {
+                closed.insert(issue.number);
+            }
+            let title = issue.title.as_str();
+            match issue.fingerprint {
+                Some(ref fingerprint) => {
+                    fingerprint_to_number
+                        .insert(fingerprint.as_str(), issue.number);
+                    if !fingerprints.contains(fingerprint) {
+                        orphan_title_to_numbers
+                            .entry(title)
+                            .or_insert_with(Vec::new)
+                            .push(issue.number);
+                    }
+                }
+                None => {
+                    title_to_number.insert(title, issue.number);
+                }
+            };
+        }
+
+        Previous {
+            fingerprint_to_number,
+            title_to_number,
+            orphan_title_to_numbers,
+            closed,
+        }
+    }
+
+    fn find(&mut self, todo: &Todo, args: &Args) -> Option<usize> {
+        //! Returns the number of the issue previously opened for the todo.
+        if let Some(number) = self.fingerprint_to_number.get(&*todo.fingerprint)
+        {
+            return Some(*number);
+        }
+
+        let title = todo.get_title(args);
+        if let Some(number) = self.title_to_number.get(title.as_str()) {
+            return Some(*number);
+        }
+        self.orphan_title_to_numbers.get_mut(title.as_str())?.pop()
+    }
 
-    Ok(issues_in_file)
+    fn is_closed(&self, number: usize) -> bool {
+        self.closed.contains(&number)
+    }
 }
 
-fn contains_todo(line: &str) -> bool {
-    //! Returns if a line contains a todo comment.
-    //! Looks for both C and Bash style comments.
-    let comment = match line.find("//") {
-        Some(value) => Some(value),
-        None => line.find("#"),
+fn get_continuation(
+    continuation: &Continuation,
+    line: &str,
+    comments: &[syntax::Comment],
+    args: &Args,
+) -> Option<String> {
+    //! Returns the text of a line if it continues the description of the
+    //! previous todo, i.e. it is either
+    //! - a line comment at the same indentation with nothing before it
+    //! - the next line of the block comment the todo was found in
+    //!
+    //! Lines that contain a todo or a directive of their own never continue
+    //! a description.
+    let comment = comments.first()?;
+    if find_marker(comment.text, args).is_some()
+        || find_directive(comment.text).is_some()
+    {
+        return None;
+    }
+
+    match continuation {
+        Continuation::Line(column) => {
+            let is_aligned = !comment.is_block
+                && comment.column == *column
+                && line[..*column].trim().is_empty();
+            match is_aligned {
+                true => Some(comment.text.trim().to_string()),
+                false => None,
+            }
+        }
+        Continuation::Block if comment.column == 0 && comment.is_block => {
+            // Strip the leading asterisk used to decorate C style blocks.
+            let text = comment.text.trim();
+            Some(text.strip_prefix('*').unwrap_or(text).trim().to_string())
+        }
+        _ => None,
+    }
+}
+
+fn find_marker(comment: &str, args: &Args) -> Option<(Marker, usize)> {
+    //! Returns the first marker the text of a comment contains along with
+    //! the byte index right after its keyword.
+    //!
+    //! Returns None if the comment does not contain a marker.
+    let (index, marker) = args
+        .get_markers()
+        .iter()
+        .filter_map(|marker| {
+            let index = marker.find(comment, args.is_ignore_case())?;
+            Some((index, marker))
+        })
+        .min_by_key(|(index, _)| *index)?;
+
+    Some((marker.clone(), index + marker.get_keyword().len()))
+}
+
+fn extract_title(after_marker: &str) -> String {
+    //! Parses the remainder of a comment after its marker and returns
+    //! the String to be used as the title of a new GitHub issue.
+    //!
+    //! A parenthesized note such as an issue reference or username directly
+    //! after the marker, e.g. TODO(#123): title, is not part of the title.
+    let after_marker = match after_marker.starts_with('(') {
+        true => match after_marker.find(')') {
+            Some(end) => &after_marker[end + 1..],
+            None => after_marker,
+        },
+        false => after_marker,
     };
+    let title = after_marker
+        .strip_prefix(':')
+        .unwrap_or(after_marker)
+        .trim();
 
-    let todo = line.find(TODO);
-    if comment.is_some() && todo.is_some() {
-        let comment_index = comment.unwrap();
-        let todo_index = todo.unwrap();
-        return todo_index > comment_index;
+    title.to_string()
+}
+
+fn has_no_issue_note(after_marker: &str) -> bool {
+    //! Whether the parenthesized note directly after a marker asks for no
+    //! issue to be opened, e.g. "(no-issue): title" or
+    //! "(someone, no-issue): title".
+    let note = match after_marker.strip_prefix('(') {
+        Some(rest) => rest.split(')').next().unwrap_or(""),
+        None => return false,
+    };
+
+    note.split(',')
+        .any(|part| part.trim().eq_ignore_ascii_case(NO_ISSUE_NOTE))
+}
+
+fn find_directive(comment: &str) -> Option<Directive> {
+    //! Parses a directive written in a comment, e.g.
+    //! "todo-to-issue: ignore-file".
+    let start = comment.find(DIRECTIVE_PREFIX)? + DIRECTIVE_PREFIX.len();
+    match comment[start..].split_whitespace().next()? {
+        "ignore-next-line" => Some(Directive::IgnoreNextLine),
+        "ignore-file" => Some(Directive::IgnoreFile),
+        _ => None,
     }
+}
+
+fn get_reference(after_marker: &str) -> Option<usize> {
+    //! Parses an issue reference written directly after a marker,
+    //! e.g. "(#123): title" or "(#123, someone): title".
+    //!
+    //! Returns the referenced issue number if there is one.
+    let rest = after_marker.strip_prefix("(#")?;
+    let end = rest.find([')', ','])?;
 
-    false
+    rest[..end].trim().parse().ok()
 }
 
-fn extract_title(line: &str) -> String {
-    //! Parses a line containing a todo comment and returns the
-    //! remainder of the String after "todo" to be used as the title of a
-    //! new GitHub issue.
-    let vec: Vec<&str> = line.split(TODO).collect();
-    let after_todo = vec[1];
-    let title = if after_todo.starts_with(":") {
-        &after_todo[1..]
-    } else {
-        after_todo
+pub fn write_reference(location: &Location, number: usize) -> io::Result<()> {
+    //! Rewrites the todo at the given location in place to reference an
+    //! issue, e.g. "// TODO: foo" becomes "// TODO(#123): foo".
+    //!
+    //! Only the reference is inserted so the rest of the file, including
+    //! its line endings and encoding, is left untouched.
+    let mut bytes = fs::read(&location.file)?;
+
+    // Offsets are those of the decoded text, which only match the bytes of
+    // UTF-8 files once their byte order mark is skipped.
+    let encoding = Encoding::detect(&bytes);
+    if encoding.is_utf16() {
+        return Err(io::Error::from(io::ErrorKind::InvalidData));
+    }
+    let mut line_start = encoding.get_bom_length();
+    for _ in 1..location.line_number {
+        match bytes[line_start..].iter().position(|&b| b == b'\n') {
+            Some(index) => line_start += index + 1,
+            None => return Err(io::Error::from(io::ErrorKind::InvalidData)),
+        }
     }
-    .trim();
 
-    title.to_string()
+    // Make sure the file has not changed since it was searched.
+    let index = line_start + location.column;
+    let keyword = location.keyword.as_bytes();
+    let is_unchanged = index <= bytes.len()
+        && index >= line_start + keyword.len()
+        && bytes[index - keyword.len()..index].eq_ignore_ascii_case(keyword);
+    if !is_unchanged {
+        return Err(io::Error::from(io::ErrorKind::InvalidData));
+    }
+
+    match bytes[index..].starts_with(b"(") {
+        // Keep any existing parenthesized note, e.g. TODO(#123, someone).
+        true => {
+            let reference = format!("#{}, ", number).into_bytes();
+            bytes.splice(index + 1..index + 1, reference);
+        }
+        false => {
+            let reference = format!("(#{})", number).into_bytes();
+            bytes.splice(index..index, reference);
+        }
+    };
+
+    fs::write(&location.file, bytes)
 }
 
-fn create_body(line_number: &u32, file_path: &str) -> String {
-    //! Creates a generic description for a new GitHub issue
-    //! based on a "todo" comment.
-    format!(
-        "Found a TODO comment on line {} of file {}",
-        line_number, file_path
-    )
-    .to_string()
+impl Todo {
+    pub fn get_body(&self, args: &Args) -> String {
+        //! Creates a description for a new issue based on the comment,
+        //! starting with any explanation written below it and ending with a
+        //! permalink to the line and a snippet of the code around it.
+        //!
+        //! If a body template is set for the todo's marker, it is filled
+        //! in instead (see Todo::get_variables).
+        let keyword = self.marker.get_keyword();
+        if let Some(template) = args.get_body_template(keyword) {
+            return template.render(&self.get_variables(args));
+        }
+
+        let location = format!(
+            "Found a {} comment on line {} of file {}",
+            self.marker.get_keyword(),
+            self.line_number,
+            self.file
+        );
+        let location = match self.blame {
+            Some(ref blame) => format!(
+                "{}\n\nAdded by {} ({}) in commit {} on {}",
+                location, blame.author, blame.email, blame.commit, blame.date
+            ),
+            None => location,
+        };
+        let mut sections = Vec::new();
+        let description = self.description.join("\n");
+        if !description.trim().is_empty() {
+            sections.push(description);
+        }
+        sections.push(location);
+        sections.extend(self.get_permalink(args));
+        if !self.snippet.is_empty() {
+            sections.push(self.get_snippet());
+        }
+
+        sections.join("\n\n")
+    }
+
+    pub fn get_title(&self, args: &Args) -> String {
+        //! Returns the title of the todo's issue, filling in the title
+        //! template of its marker if one is set and does not end up empty.
+        let keyword = self.marker.get_keyword();
+        let title = match args.get_title_template(keyword) {
+            Some(template) => template.render(&self.get_variables(args)),
+            None => return self.title.clone(),
+        };
+
+        match title.trim() {
+            "" => self.title.clone(),
+            title => title.to_string(),
+        }
+    }
+
+    fn get_variables(&self, args: &Args) -> HashMap<&'static str, String> {
+        //! Returns the value of every template placeholder for the todo
+        //! (see template::VARIABLES), leaving out the ones that are unknown.
+        let mut values = HashMap::new();
+        values.insert("title", self.title.clone());
+        values.insert("marker", self.marker.get_keyword().to_string());
+        values.insert("label", self.marker.get_label().to_string());
+        values.insert("file", self.file.clone());
+        values.insert("line", self.line_number.to_string());
+        values.insert("column", self.column_number.to_string());
+        values.insert("description", self.description.join("\n"));
+        if !self.snippet.is_empty() {
+            values.insert("snippet", self.get_snippet());
+        }
+        if let Some(permalink) = self.get_permalink(args) {
+            values.insert("permalink", permalink);
+        }
+        if let Some(ref blame) = self.blame {
+            values.insert("author", blame.author.clone());
+            values.insert("email", blame.email.clone());
+            values.insert("commit", blame.commit.clone());
+            values.insert("date", blame.date.clone());
+        }
+
+        values
+    }
+
+    pub fn get_permalink(&self, args: &Args) -> Option<String> {
+        //! Returns the url of the todo's line in the commit that last
+        //! changed it, or None if it is not committed or there is no remote.
+        let blame = self.blame.as_ref()?;
+        let remote = args.get_remote()?;
+
+        Some(args.get_tracker().get_permalink(
+            remote,
+            &blame.commit,
+            &blame.path,
+            blame.line_number,
+        ))
+    }
+
+    fn get_snippet(&self) -> String {
+        //! Returns the lines around the todo as a markdown code block
+        //! highlighted based on the file's language.
+        //!
+        //! The fence is made longer than any run of backticks in the code
+        //! so it cannot be closed early.
+        let code = self.snippet.join("\n");
+        let mut longest = 0;
+        let mut run = 0;
+        for c in code.chars() {
+            run = if c == '`' { run + 1 } else { 0 };
+            longest = longest.max(run);
+        }
+        let fence = "`".repeat(longest.max(2) + 1);
+
+        format!(
+            "{}{}\n{}\n{}",
+            fence,
+            syntax::get_language_hint(&self.file),
+            code,
+            fence
+        )
+    }
+
+    pub fn is_new(&self) -> bool {
+        //! Whether an issue should be opened for the todo.
+        !self.is_tracked && !self.is_ignored
+    }
+
+    fn to_issue(&self, args: &Args) -> Issue {
+        //! Creates the Issue to open for the todo.
+        let body = self.get_body(args);
+        let mut labels = vec![self.marker.get_label().to_string()];
+        labels.extend_from_slice(args.get_extra_labels());
+        let location = Location {
+            file: self.file.clone(),
+            line_number: self.line_number,
+            column: self.offset,
+            keyword: self.marker.get_keyword().to_string(),
+            text: self.title.clone(),
+        };
+
+        let mut assignees = args.get_assignees().to_vec();
+        let login = self.blame.as_ref().and_then(|b| args.get_login(&b.email));
+        if let Some(login) = login {
+            if !assignees.iter().any(|assignee| assignee == login) {
+                assignees.push(login.to_string());
+            }
+        }
+
+        Issue::new(self.get_title(args), body, labels)
+            .with_assignees(assignees)
+            .with_location(location)
+            .with_fingerprint(self.fingerprint.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find_todos(path: &str, contents: &str) -> Vec<Todo> {
+        find_todos_in_bytes(path, contents.as_bytes())
+    }
+
+    fn find_todos_in_bytes(path: &str, bytes: &[u8]) -> Vec<Todo> {
+        find_todos_in_file(path, bytes, false, &Args::with_defaults())
+    }
+
+    fn to_utf16(text: &str, to_bytes: fn(u16) -> [u8; 2]) -> Vec<u8> {
+        text.encode_utf16().flat_map(to_bytes).collect()
+    }
+
+    fn get_positions(todos: &[Todo]) -> Vec<(&str, u32, usize)> {
+        todos
+            .iter()
+            .map(|t| (t.title.as_str(), t.line_number, t.column_number))
+            .collect()
+    }
+
+    #[test]
+    fn block_comments_continue_the_description() {
+        let todos = find_todos(
+            "a.c",
+            "/* TODO: Fix this\n * first line\n *\n * second line\n */\n",
+        );
+        assert_eq!(todos.len(), 1);
+        assert_eq!(todos[0].title, "Fix this");
+        assert_eq!(todos[0].description, vec!["first line", "", "second line"]);
+    }
+
+    #[test]
+    fn closing_line_text_is_kept() {
+        let todos = find_todos("a.c", "/* TODO: Fix this\n   last line */\n");
+        assert_eq!(todos[0].description, vec!["last line"]);
+    }
+
+    #[test]
+    fn line_comments_continue_the_description() {
+        let todos = find_todos(
+            "a.py",
+            "# TODO: Fix this\n# more text\nx = 1  # unrelated\n",
+        );
+        assert_eq!(todos[0].description, vec!["more text"]);
+    }
+
+    #[test]
+    fn positions_are_those_of_the_decoded_text() {
+        let text = "x = 1\r\n// é TODO: First\r\n// more\r\n\
+                    \r\n  // TODO: Second\r\n";
+        let expected = vec![("First", 2, 6), ("Second", 5, 6)];
+
+        let le = to_utf16(&format!("\u{feff}{}", text), u16::to_le_bytes);
+        let todos = find_todos_in_bytes("a.rs", &le);
+        assert_eq!(get_positions(&todos), expected);
+        assert_eq!(todos[0].description, vec!["more"]);
+
+        let be = to_utf16(&format!("\u{feff}{}", text), u16::to_be_bytes);
+        let todos = find_todos_in_bytes("a.rs", &be);
+        assert_eq!(get_positions(&todos), expected);
+
+        // Neither the byte order mark nor the carriage returns are kept.
+        let todos = find_todos("a.rs", &format!("\u{feff}{}", text));
+        assert_eq!(get_positions(&todos), expected);
+        assert_eq!(todos[0].description, vec!["more"]);
+        assert_eq!(todos[0].snippet[1], "// é TODO: First");
+    }
+
+    #[test]
+    fn latin1_files_are_searched() {
+        // "café" and "déjà" in Latin-1
+        let todos = find_todos_in_bytes(
+            "a.rs",
+            b"// caf\xe9 TODO: First\n/* d\xe9j\xe0 */ // TODO: Second\n",
+        );
+        assert_eq!(
+            get_positions(&todos),
+            vec![("First", 1, 9), ("Second", 2, 15)]
+        );
+    }
+
+    fn write_references(
+        name: &str,
+        contents: &[u8],
+        numbers: &[usize],
+    ) -> (Vec<io::Result<()>>, Vec<u8>) {
+        //! Searches a temporary file with the contents, references the
+        //! numbers in its todos and returns the results and new contents.
+        let path = std::env::temp_dir().join(format!(
+            "todo-to-issue-{}-{}.rs",
+            std::process::id(),
+            name
+        ));
+        let path = path.to_str().unwrap();
+        fs::write(path, contents).unwrap();
+        let args = Args::with_defaults();
+        let results = find_todos_in_bytes(path, contents)
+            .iter()
+            .zip(numbers)
+            .map(|(todo, number)| {
+                let issue = todo.to_issue(&args);
+                write_reference(issue.get_location().unwrap(), *number)
+            })
+            .collect();
+        let contents = fs::read(path).unwrap();
+        fs::remove_file(path).unwrap();
+
+        (results, contents)
+    }
+
+    #[test]
+    fn references_are_written_after_the_marker() {
+        let (results, contents) = write_references(
+            "bom",
+            "\u{feff}// TODO: First\r\nlet a = 1;\r\n\
+             /* é */ // TODO(someone): Second\r\n"
+                .as_bytes(),
+            &[1, 22],
+        );
+        assert!(results.iter().all(|result| result.is_ok()));
+        assert_eq!(
+            str::from_utf8(&contents).unwrap(),
+            "\u{feff}// TODO(#1): First\r\nlet a = 1;\r\n\
+             /* é */ // TODO(#22, someone): Second\r\n"
+        );
+
+        let (results, contents) =
+            write_references("latin1", b"// TODO: caf\xe9\n", &[3]);
+        assert!(results[0].is_ok());
+        assert_eq!(contents, b"// TODO(#3): caf\xe9\n");
+    }
+
+    #[test]
+    fn references_are_not_written_at_wrong_offsets() {
+        // Replacement characters take more bytes than the Latin-1 ones.
+        let latin1: &[u8] = b"/* caf\xe9 */ // TODO: First\n";
+        let (results, contents) = write_references("before", latin1, &[1]);
+        assert!(results[0].is_err());
+        assert_eq!(contents, latin1);
+
+        let le = to_utf16("\u{feff}// TODO: First\n", u16::to_le_bytes);
+        let (results, contents) = write_references("utf16", &le, &[1]);
+        assert!(results[0].is_err());
+        assert_eq!(contents, le);
+    }
+
+    fn get_ignored(todos: &[Todo]) -> Vec<(&str, bool)> {
+        todos
+            .iter()
+            .map(|todo| (todo.title.as_str(), todo.is_ignored))
+            .collect()
+    }
+
+    #[test]
+    fn no_issue_notes_ignore_todos() {
+        let todos = find_todos(
+            "a.rs",
+            "// TODO(no-issue): First\n\
+             let a = 1;\n\
+             // TODO(someone, No-Issue): Second\n\
+             let b = 1;\n\
+             // TODO(someone): Third\n\
+             let c = 1;\n\
+             // TODO: Fourth (no-issue)\n\
+             let d = 1;\n\
+             // TODO(no-issues): Fifth\n",
+        );
+        assert_eq!(
+            get_ignored(&todos),
+            vec![
+                ("First", true),
+                ("Second", true),
+                ("Third", false),
+                ("Fourth (no-issue)", false),
+                ("Fifth", false),
+            ]
+        );
+    }
+
+    #[test]
+    fn directives_ignore_the_next_line_or_the_file() {
+        let todos = find_todos(
+            "a.rs",
+            "// todo-to-issue: ignore-next-line\n\
+             let a = 1; // TODO: First\n\
+             let b = 1; // TODO: Second\n\
+             let c = 1; // todo-to-issue: ignore-next-line\n\
+             /* TODO: Third */\n\
+             // todo-to-issue: ignore-nothing\n\
+             let d = 1; // TODO: Fourth\n",
+        );
+        assert_eq!(
+            get_ignored(&todos),
+            vec![
+                ("First", true),
+                ("Second", false),
+                ("Third", true),
+                ("Fourth", false),
+            ]
+        );
+
+        // The directive applies to the whole file wherever it is.
+        let todos = find_todos(
+            "a.py",
+            "# TODO: First\nx = 1\n# TODO: Second\n\
+             # todo-to-issue: ignore-file\n",
+        );
+        assert_eq!(
+            get_ignored(&todos),
+            vec![("First", true), ("Second", true)]
+        );
+    }
+
+    #[test]
+    fn only_untracked_ignored_todos_are_counted() {
+        let mut file_to_todos = BTreeMap::new();
+        file_to_todos.insert(
+            "a.rs".to_string(),
+            find_todos(
+                "a.rs",
+                "// TODO(no-issue): Ignored\nlet a = 1;\n\
+                 // TODO(#3, no-issue): Tracked\nlet b = 1;\n\
+                 // TODO: New\n",
+            ),
+        );
+        file_to_todos.insert(
+            "b.py".to_string(),
+            find_todos("b.py", "# todo-to-issue: ignore-file\n# TODO: B\n"),
+        );
+        assert_eq!(count_ignored(&file_to_todos), 2);
+    }
+
+    fn opened(title: &str, number: usize, fingerprint: &str) -> Opened {
+        Opened {
+            title: title.to_string(),
+            number,
+            fingerprint: Some(fingerprint.to_string()),
+            file: None,
+            is_open: true,
+        }
+    }
+
+    #[test]
+    fn fingerprints_ignore_the_code_around_a_todo() {
+        let before = find_todos("a.rs", "// TODO: Fix this\nlet a = 1;\n");
+        let after = find_todos("a.rs", "\n// TODO: Fix this\nlet a = 2;\n");
+        assert_eq!(before[0].fingerprint, after[0].fingerprint);
+        let other = find_todos("b.rs", "// TODO: Fix this\nlet a = 1;\n");
+        assert_ne!(before[0].fingerprint, other[0].fingerprint);
+    }
+
+    #[test]
+    fn fingerprints_tell_apart_todos_with_the_same_title() {
+        let todos = find_todos(
+            "a.rs",
+            "// TODO: Fix\nlet a = 1;\n// TODO: Fix\nlet b = 1;\n\
+             // TODO: Fix\nlet b = 1;\n",
+        );
+        assert_ne!(todos[0].fingerprint, todos[1].fingerprint);
+        assert_ne!(todos[1].fingerprint, todos[2].fingerprint);
+        assert_ne!(todos[0].fingerprint, todos[2].fingerprint);
+
+        // The context starts after the comment, not its closing line.
+        let todos = find_todos(
+            "a.c",
+            "/* TODO: Fix\n */\nint a;\n/* TODO: Fix\n */\nint b;\n",
+        );
+        assert_ne!(todos[0].fingerprint, todos[1].fingerprint);
+    }
+
+    #[test]
+    fn orphaned_issues_are_matched_by_title_once() {
+        let args = Args::with_defaults();
+        let mut todos =
+            find_todos("new.rs", "// TODO: Moved\n// TODO: Moved\n");
+        let issues = vec![opened("Moved", 1, "0000000000000000")];
+        let fingerprints =
+            todos.iter().map(|todo| todo.fingerprint.clone()).collect();
+        let mut previous = Previous::new(&issues, &fingerprints);
+        track_todos(&mut todos, &mut previous, &args);
+        assert_eq!(todos[0].issue, Some(1));
+        assert_eq!(todos[1].issue, None);
+    }
+
+    #[test]
+    fn matched_issues_are_not_matched_by_title() {
+        let args = Args::with_defaults();
+        let mut todos = find_todos("a.rs", "// TODO: Same\n");
+        let mut moved = find_todos("b.rs", "// TODO: Same\n");
+        let issues = vec![opened("Same", 1, &todos[0].fingerprint)];
+        let fingerprints = todos
+            .iter()
+            .chain(&moved)
+            .map(|todo| todo.fingerprint.clone())
+            .collect();
+        let mut previous = Previous::new(&issues, &fingerprints);
+        track_todos(&mut todos, &mut previous, &args);
+        track_todos(&mut moved, &mut previous, &args);
+        assert_eq!(todos[0].issue, Some(1));
+        assert_eq!(moved[0].issue, None);
+    }
+
+    #[test]
+    fn only_issues_of_searched_or_deleted_files_are_removed() {
+        let mut issues = vec![
+            opened("Removed", 1, "0000000000000001"),
+            opened("Kept", 2, "0000000000000002"),
+            opened("Skipped", 3, "0000000000000003"),
+            opened("Closed", 4, "0000000000000004"),
+            opened("Written by hand", 5, ""),
+            opened("Deleted", 6, "0000000000000006"),
+        ];
+        let files = ["a.rs", "a.rs", "b.rs", "a.rs", "", "c.rs"];
+        for (issue, file) in issues.iter_mut().zip(&files) {
+            issue.file = Some(file.to_string());
+        }
+        issues[3].is_open = false;
+        issues[4].fingerprint = None;
+        issues[4].file = None;
+
+        // b.rs was skipped and c.rs no longer exists.
+        let files = vec!["a.rs".to_string(), "b.rs".to_string()];
+        let mut kept = find_todos("a.rs", "// TODO: Kept\n");
+        kept[0].issue = Some(2);
+        let mut file_to_todos = BTreeMap::new();
+        file_to_todos.insert("a.rs".to_string(), kept);
+
+        let removed = get_removed_issues(&issues, &files, &file_to_todos);
+        let numbers: Vec<usize> = removed.iter().map(|i| i.number).collect();
+        assert_eq!(numbers, vec![1, 6]);
+    }
 }
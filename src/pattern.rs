@@ -0,0 +1,233 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use glob::MatchOptions;
+
+/// Name of the file at the root of the repository listing files to never
+/// search, one pattern per line.
+pub const IGNORE_FILE: &str = ".todoignore";
+
+const OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+/// A glob pattern of files with the semantics of a line of .gitignore:
+///
+/// - A leading ! negates the pattern, i.e. files it matches are included
+///   again by an exclude pattern or left out by an include pattern
+/// - A trailing / only matches directories
+/// - Patterns without a / in the middle or at the start match the name of
+///   a file or directory at any depth, e.g. *.min.js
+/// - Other patterns are matched against the whole path, e.g. src/*.rs
+///   does not match src/a/b.rs but src/**/*.rs does
+///
+/// Patterns matching a directory match every file inside it.
+pub struct Pattern {
+    glob: glob::Pattern,
+    /// The pattern as it was written.
+    source: String,
+    is_negated: bool,
+    is_anchored: bool,
+    is_directory: bool,
+}
+
+impl Pattern {
+    pub fn parse(source: &str) -> Result<Pattern, String> {
+        //! Returns an error message naming the pattern if it is not a valid
+        //! glob, e.g. "[a-".
+        let mut glob = source.trim();
+        let is_negated = glob.starts_with('!');
+        if is_negated {
+            glob = &glob[1..];
+        }
+        let is_directory = glob.ends_with('/');
+        glob = glob.trim_end_matches('/');
+        let is_anchored = glob.contains('/');
+        glob = glob.trim_start_matches('/');
+        if glob.is_empty() {
+            return Err(format!("Invalid glob pattern \"{}\".", source));
+        }
+
+        let glob = glob::Pattern::new(glob).map_err(|e| {
+            format!("Invalid glob pattern \"{}\": {}.", source, e.msg)
+        })?;
+
+        Ok(Pattern {
+            glob,
+            source: source.trim().to_string(),
+            is_negated,
+            is_anchored,
+            is_directory,
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    fn matches(&self, file: &str) -> bool {
+        //! Returns whether the pattern matches the file or any of the
+        //! directories it is in.
+        let file = file.trim_start_matches("./");
+        let mut ends: Vec<usize> =
+            file.match_indices('/').map(|(index, _)| index).collect();
+        ends.push(file.len());
+        for end in ends {
+            let is_directory = end < file.len();
+            if self.is_directory && !is_directory {
+                continue;
+            }
+
+            let path = &file[..end];
+            let candidate = match self.is_anchored {
+                true => path,
+                false => path.rsplit('/').next().unwrap_or(path),
+            };
+            if self.glob.matches_with(candidate, OPTIONS) {
+                return true;
+            }
+        }
+
+        false
+    }
+}
+
+pub fn find_match(patterns: &[Pattern], file: &str) -> Option<bool> {
+    //! Returns whether the last of the patterns that matches the file is
+    //! not negated, or None if none of them match it.
+    patterns
+        .iter()
+        .rev()
+        .find(|pattern| pattern.matches(file))
+        .map(|pattern| !pattern.is_negated)
+}
+
+pub fn parse_all(values: &[String]) -> Result<Vec<Pattern>, String> {
+    //! Parses a list of patterns, returning an error message naming the
+    //! first invalid one.
+    values.iter().map(|value| Pattern::parse(value)).collect()
+}
+
+pub fn read_ignore_file(path: &Path) -> Result<Vec<Pattern>, String> {
+    //! Parses the patterns of an ignore file, skipping blank lines and
+    //! comments starting with #.
+    //!
+    //! Returns no patterns if the file does not exist, or an error message
+    //! naming the line of the first invalid pattern.
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => {
+            return Ok(Vec::new())
+        }
+        Err(e) => {
+            return Err(format!("Unable to read {}: {}", path.display(), e))
+        }
+    };
+
+    let mut patterns = Vec::new();
+    for (index, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let pattern = Pattern::parse(line).map_err(|e| {
+            format!("{} On line {} of {}.", e, index + 1, path.display())
+        })?;
+        patterns.push(pattern);
+    }
+
+    Ok(patterns)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(values: &[&str]) -> Vec<Pattern> {
+        let values: Vec<String> =
+            values.iter().map(|v| v.to_string()).collect();
+        parse_all(&values).unwrap()
+    }
+
+    fn is_match(values: &[&str], file: &str) -> Option<bool> {
+        find_match(&parse(values), file)
+    }
+
+    #[test]
+    fn patterns_without_a_slash_match_names_at_any_depth() {
+        assert_eq!(is_match(&["*.min.js"], "a.min.js"), Some(true));
+        assert_eq!(is_match(&["*.min.js"], "dist/js/a.min.js"), Some(true));
+        assert_eq!(is_match(&["*.min.js"], "a.js"), None);
+        // Every file inside a matching directory is matched as well.
+        assert_eq!(is_match(&["build"], "a/build/b/c.rs"), Some(true));
+    }
+
+    #[test]
+    fn patterns_with_a_slash_match_from_the_root() {
+        assert_eq!(is_match(&["src/*.rs"], "src/a.rs"), Some(true));
+        assert_eq!(is_match(&["src/*.rs"], "src/a/b.rs"), None);
+        assert_eq!(is_match(&["src/*.rs"], "lib/src/a.rs"), None);
+        assert_eq!(is_match(&["/main.rs"], "main.rs"), Some(true));
+        assert_eq!(is_match(&["/main.rs"], "src/main.rs"), None);
+        assert_eq!(is_match(&["./src/*.rs"], "./src/a.rs"), None);
+    }
+
+    #[test]
+    fn double_stars_match_any_number_of_directories() {
+        assert_eq!(is_match(&["src/**/*.rs"], "src/a.rs"), Some(true));
+        assert_eq!(is_match(&["src/**/*.rs"], "src/a/b/c.rs"), Some(true));
+        assert_eq!(is_match(&["**/fixtures"], "a/b/fixtures/c"), Some(true));
+        assert_eq!(is_match(&["src/**"], "src/a/b.rs"), Some(true));
+        assert_eq!(is_match(&["src/**"], "lib/a.rs"), None);
+    }
+
+    #[test]
+    fn trailing_slashes_only_match_directories() {
+        assert_eq!(is_match(&["vendor/"], "vendor/a.rs"), Some(true));
+        assert_eq!(is_match(&["vendor/"], "lib/vendor/a/b.rs"), Some(true));
+        assert_eq!(is_match(&["vendor/"], "vendor"), None);
+        assert_eq!(is_match(&["vendor/"], "lib/vendor"), None);
+        assert_eq!(is_match(&["/vendor/"], "lib/vendor/a.rs"), None);
+    }
+
+    #[test]
+    fn the_last_matching_pattern_wins() {
+        let patterns = ["tests/", "!tests/fixtures/"];
+        assert_eq!(is_match(&patterns, "tests/a.rs"), Some(true));
+        assert_eq!(is_match(&patterns, "tests/fixtures/a.rs"), Some(false));
+        assert_eq!(is_match(&patterns, "src/a.rs"), None);
+
+        let patterns = ["!tests/fixtures/", "tests/"];
+        assert_eq!(is_match(&patterns, "tests/fixtures/a.rs"), Some(true));
+    }
+
+    #[test]
+    fn invalid_patterns_are_named() {
+        let error = Pattern::parse("src/[a-").err().unwrap();
+        assert!(error.starts_with("Invalid glob pattern \"src/[a-\""));
+        assert!(Pattern::parse("/").is_err());
+        assert!(Pattern::parse("!").is_err());
+    }
+
+    #[test]
+    fn ignore_files_skip_comments_and_name_invalid_lines() {
+        let path = std::env::temp_dir()
+            .join(format!("todoignore-{}", std::process::id()));
+        let contents = "# Generated\n\ndist/\n  *.min.js  \n!keep.min.js\n";
+        fs::write(&path, contents).unwrap();
+        let patterns = read_ignore_file(&path).unwrap();
+        let sources: Vec<&str> = patterns.iter().map(|p| p.as_str()).collect();
+        assert_eq!(sources, vec!["dist/", "*.min.js", "!keep.min.js"]);
+
+        fs::write(&path, "dist/\n# [\nsrc/[a-\n").unwrap();
+        let error = read_ignore_file(&path).err().unwrap();
+        fs::remove_file(&path).unwrap();
+        assert!(error.contains("src/[a-"), "{}", error);
+        assert!(error.contains("On line 3 of"), "{}", error);
+
+        assert!(read_ignore_file(&path).unwrap().is_empty());
+    }
+}
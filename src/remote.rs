@@ -0,0 +1,161 @@
+/// The repository a git remote points to, e.g. github.com,
+/// sergeichestakov and todo-to-issue.
+#[derive(Clone, Default)]
+pub struct Remote {
+    /// Host of the remote, including the port for http(s) remotes.
+    pub host: String,
+    /// Owner of the repository, which may span several segments on
+    /// GitLab e.g. :group/:subgroup
+    pub owner: String,
+    pub repo: String,
+}
+
+impl Remote {
+    pub fn parse(url: &str) -> Option<Remote> {
+        //! Parses a remote url of any of the forms
+        //!
+        //! - https://github.com/:owner/:repo.git
+        //! - ssh://git@github.com:22/:owner/:repo.git
+        //! - git://github.com/:owner/:repo.git
+        //! - git@github.com::owner/:repo.git (scp-like syntax)
+        //!
+        //! Credentials and the .git suffix are optional.
+        //!
+        //! Returns None if the url does not contain a host, an owner and a
+        //! repository, e.g. for local paths and file:// urls.
+        let url = url.trim();
+        let (host, path) = match url.split_once("://") {
+            Some(("file", _)) => return None,
+            Some((scheme, rest)) => {
+                let (authority, path) = rest.split_once('/')?;
+                let host = Self::strip_user(authority);
+                // The port is only part of the web url for http(s) remotes.
+                let host = match scheme {
+                    "http" | "https" => host,
+                    _ => host.split(':').next().unwrap_or(host),
+                };
+                (host, path)
+            }
+            None => {
+                // scp-like syntax is only recognized if there is no slash
+                // before the first colon, otherwise it is a local path.
+                // A single letter is a Windows drive, e.g. C:/repo.
+                let (authority, path) = url.split_once(':')?;
+                if authority.contains('/') || authority.len() == 1 {
+                    return None;
+                }
+                (Self::strip_user(authority), path)
+            }
+        };
+
+        let path = path.trim_matches('/');
+        let path = path.strip_suffix(".git").unwrap_or(path);
+        let (owner, repo) = path.rsplit_once('/')?;
+        if host.is_empty() || owner.is_empty() || repo.is_empty() {
+            return None;
+        }
+
+        Some(Remote {
+            host: host.to_string(),
+            owner: owner.to_string(),
+            repo: repo.to_string(),
+        })
+    }
+
+    fn strip_user(authority: &str) -> &str {
+        //! Drops any credentials in front of the host e.g. user:pass@host
+        authority.rsplit('@').next().unwrap_or(authority)
+    }
+
+    pub fn get_path(&self) -> String {
+        //! Returns the path of the repository on the host
+        //! e.g. :owner/:repo
+        format!("{}/{}", self.owner, self.repo)
+    }
+
+    pub fn get_web_url(&self) -> String {
+        format!("https://{}/{}", self.host, self.get_path())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(url: &str) -> (String, String, String) {
+        let remote = Remote::parse(url).expect("url should be parsed");
+        (remote.host, remote.owner, remote.repo)
+    }
+
+    fn expected(
+        host: &str,
+        owner: &str,
+        repo: &str,
+    ) -> (String, String, String) {
+        (host.to_string(), owner.to_string(), repo.to_string())
+    }
+
+    #[test]
+    fn https_urls_are_parsed() {
+        let github = expected("github.com", "owner", "repo");
+        assert_eq!(parse("https://github.com/owner/repo.git"), github);
+        assert_eq!(parse("https://github.com/owner/repo"), github);
+        assert_eq!(parse("https://github.com/owner/repo/\n"), github);
+        assert_eq!(
+            parse("https://user:p@ss@git.example.com:8443/owner/repo.git"),
+            expected("git.example.com:8443", "owner", "repo")
+        );
+    }
+
+    #[test]
+    fn ssh_and_git_urls_are_parsed() {
+        let github = expected("github.com", "owner", "repo");
+        assert_eq!(parse("ssh://git@github.com:22/owner/repo.git"), github);
+        assert_eq!(parse("ssh://github.com/owner/repo"), github);
+        assert_eq!(parse("git://github.com/owner/repo.git"), github);
+    }
+
+    #[test]
+    fn scp_like_urls_are_parsed() {
+        let github = expected("github.com", "owner", "repo");
+        assert_eq!(parse("git@github.com:owner/repo.git"), github);
+        assert_eq!(parse("github.com:owner/repo"), github);
+    }
+
+    #[test]
+    fn only_the_git_suffix_is_stripped() {
+        assert_eq!(
+            parse("git@github.com:owner/owner.github.io.git"),
+            expected("github.com", "owner", "owner.github.io")
+        );
+        assert_eq!(
+            parse("https://github.com/owner/repo.git.git"),
+            expected("github.com", "owner", "repo.git")
+        );
+    }
+
+    #[test]
+    fn gitlab_subgroups_are_part_of_the_owner() {
+        let remote = Remote::parse("git@gitlab.com:group/sub/repo.git");
+        let remote = remote.expect("url should be parsed");
+        assert_eq!(remote.owner, "group/sub");
+        assert_eq!(remote.get_path(), "group/sub/repo");
+        assert_eq!(remote.get_web_url(), "https://gitlab.com/group/sub/repo");
+    }
+
+    #[test]
+    fn local_paths_are_not_remotes() {
+        for url in &[
+            "/home/user/repo.git",
+            "../repo",
+            "./dir:name/repo",
+            "C:/Users/user/repo",
+            "file:///home/user/repo.git",
+            "file://localhost/home/user/repo.git",
+            "https://github.com/repo",
+            "",
+        ] {
+            assert!(Remote::parse(url).is_none(), "{} was parsed", url);
+        }
+    }
+}
@@ -0,0 +1,205 @@
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use serde_json::{json, Value};
+
+use super::cli;
+use super::parse;
+
+use cli::Args;
+use parse::Todo;
+
+pub const FORMATS: &[&str] = &["json", "csv", "sarif"];
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const SARIF_VERSION: &str = "2.1.0";
+const CSV_HEADER: &[&str] = &[
+    "file",
+    "line",
+    "column",
+    "marker",
+    "label",
+    "title",
+    "body",
+    "author",
+    "tracked",
+    "ignored",
+    "issue",
+    "fingerprint",
+];
+
+/// Machine-readable formats the result of a scan can be output in.
+#[derive(Clone, Copy)]
+pub enum Format {
+    Json,
+    Csv,
+    /// Static Analysis Results Interchange Format, used by GitHub code
+    /// scanning.
+    Sarif,
+}
+
+impl FromStr for Format {
+    type Err = ();
+
+    fn from_str(string: &str) -> Result<Format, ()> {
+        match string {
+            "json" => Ok(Format::Json),
+            "csv" => Ok(Format::Csv),
+            "sarif" => Ok(Format::Sarif),
+            _ => Err(()),
+        }
+    }
+}
+
+pub fn create_report(
+    format: Format,
+    file_to_todos: &BTreeMap<String, Vec<Todo>>,
+    args: &Args,
+) -> String {
+    //! Formats every todo found, including the ones that are already
+    //! tracked, sorted by file and line.
+    let mut todos: Vec<&Todo> = file_to_todos.values().flatten().collect();
+    todos.sort_by(|a, b| {
+        (&a.file, a.line_number).cmp(&(&b.file, b.line_number))
+    });
+
+    match format {
+        Format::Json => {
+            let array: Vec<Value> =
+                todos.iter().map(|todo| to_json(todo, args)).collect();
+            format!("{}\n", serde_json::to_string_pretty(&array).unwrap())
+        }
+        Format::Csv => to_csv(&todos, args),
+        Format::Sarif => {
+            let sarif = to_sarif(&todos, args);
+            format!("{}\n", serde_json::to_string_pretty(&sarif).unwrap())
+        }
+    }
+}
+
+fn to_json(todo: &Todo, args: &Args) -> Value {
+    json!({
+        "file": todo.file,
+        "line": todo.line_number,
+        "column": todo.column_number,
+        "marker": todo.marker.get_keyword(),
+        "label": todo.marker.get_label(),
+        "title": todo.get_title(args),
+        "body": todo.get_body(args),
+        "author": get_author(todo),
+        "tracked": todo.is_tracked,
+        "ignored": todo.is_ignored,
+        "issue": todo.issue,
+        "fingerprint": todo.fingerprint,
+    })
+}
+
+fn get_author(todo: &Todo) -> Option<String> {
+    todo.blame.as_ref().map(|blame| blame.author.clone())
+}
+
+fn to_csv(todos: &[&Todo], args: &Args) -> String {
+    //! Creates a CSV table with a header row and one row per todo.
+    let mut csv = format!("{}\n", CSV_HEADER.join(","));
+    for todo in todos {
+        let row = [
+            todo.file.clone(),
+            todo.line_number.to_string(),
+            todo.column_number.to_string(),
+            todo.marker.get_keyword().to_string(),
+            todo.marker.get_label().to_string(),
+            todo.get_title(args),
+            todo.get_body(args),
+            get_author(todo).unwrap_or_default(),
+            todo.is_tracked.to_string(),
+            todo.is_ignored.to_string(),
+            todo.issue.map(|n| n.to_string()).unwrap_or_default(),
+            todo.fingerprint.clone(),
+        ];
+        let fields: Vec<String> =
+            row.iter().map(|field| escape_csv(field)).collect();
+        csv += &fields.join(",");
+        csv += "\n";
+    }
+
+    csv
+}
+
+fn escape_csv(field: &str) -> String {
+    //! Quotes a CSV field if it contains a separator, quote or newline,
+    //! doubling any quotes inside it.
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn to_sarif(todos: &[&Todo], args: &Args) -> Value {
+    //! Creates a SARIF log with one rule per marker and one result per todo.
+    let rules: Vec<Value> = args
+        .get_markers()
+        .iter()
+        .map(|marker| {
+            json!({
+                "id": marker.get_keyword(),
+                "shortDescription": {
+                    "text": format!("{} comment", marker.get_keyword()),
+                },
+                "properties": { "tags": [marker.get_label()] },
+            })
+        })
+        .collect();
+
+    let results: Vec<Value> = todos
+        .iter()
+        .map(|todo| {
+            json!({
+                "ruleId": todo.marker.get_keyword(),
+                "level": "note",
+                "message": {
+                    "text": format!(
+                        "{}: {}",
+                        todo.marker.get_keyword(),
+                        todo.get_title(args)
+                    ),
+                },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": { "uri": todo.file },
+                        "region": {
+                            "startLine": todo.line_number,
+                            "startColumn": todo.column_number,
+                        },
+                    },
+                }],
+                "partialFingerprints": {
+                    "todoToIssue/v1": todo.fingerprint,
+                },
+                "properties": {
+                    "body": todo.get_body(args),
+                    "author": get_author(todo),
+                    "tracked": todo.is_tracked,
+                    "ignored": todo.is_ignored,
+                    "issue": todo.issue,
+                },
+            })
+        })
+        .collect();
+
+    json!({
+        "$schema": SARIF_SCHEMA,
+        "version": SARIF_VERSION,
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": env!("CARGO_PKG_NAME"),
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": env!("CARGO_PKG_HOMEPAGE"),
+                    "rules": rules,
+                },
+            },
+            "results": results,
+        }],
+    })
+}
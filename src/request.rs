@@ -1,147 +1,270 @@
-use std::collections::HashSet;
-
-use console::style;
-use reqwest::header::AUTHORIZATION;
-use reqwest::StatusCode;
-use serde_json::json;
+use console::Term;
+use reqwest::header::{HeaderMap, LINK};
+use reqwest::{Client, Method, StatusCode, Url};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
 
 use super::cli;
-use super::issue;
 
-use issue::Issue;
+pub const PER_PAGE: &str = "100";
+
+pub fn send<T: DeserializeOwned>(
+    client: &Client,
+    method: Method,
+    url: &str,
+    headers: &HeaderMap,
+    json: &Value,
+) -> Option<T> {
+    //! Makes a request with the json as its body, e.g. a POST request to
+    //! create an issue.
+    //!
+    //! Returns the parsed response or None if the response is not
+    //! 200 OK or 201 Created or the request fails.
+    let result = client
+        .request(method, url)
+        .headers(headers.clone())
+        .json(json)
+        .send();
+    let mut response = match result {
+        Ok(response) => response,
+        Err(e) => {
+            cli::print_error(&format!("Failed to send request: {}", e));
+            return None;
+        }
+    };
 
-const API_ENDPOINT: &str = "https://api.github.com";
+    if !is_successful_response(response.status()) {
+        return None;
+    }
 
-pub struct Request {
-    client: reqwest::Client,
-    url: String,
-    remote_url: String,
-    auth_header: String,
+    response.json::<T>().ok()
 }
 
-impl Request {
-    pub fn new(token: String, remote: String) -> Request {
-        //! Creates a new request object that encapsulates the http client,
-        //! url formatted with the API endpoint and user's remote repo,
-        //! and auth header containing the user's token.
-        Request {
-            client: reqwest::Client::new(),
-            url: format!("{}/repos/{}/issues", API_ENDPOINT, remote)
-                .to_string(),
-            remote_url: format!("https://github.com/{}", remote).to_string(),
-            auth_header: format!("token {}", token).to_string(),
+pub fn get_all_pages<T: DeserializeOwned>(
+    client: &Client,
+    first_page: Url,
+    headers: &HeaderMap,
+) -> Option<Vec<T>> {
+    //! Makes GET requests starting at the given url, following the Link
+    //! header until every page has been fetched.
+    //!
+    //! Returns the items of every page or None if a response is
+    //! not 200 OK, a request fails or a page cannot be parsed.
+    let term = Term::stdout();
+    let show_progress = term.is_term() && !cli::is_quiet();
+    let mut items = Vec::new();
+    let mut next_page = Some(first_page);
+    let mut page = 0;
+    while let Some(url) = next_page {
+        page += 1;
+        if show_progress {
+            let progress = format!(
+                "Fetching page {} ({} {} so far)...",
+                page,
+                items.len(),
+                cli::handle_plural(&items.len(), "issue")
+            );
+            term.clear_line().ok();
+            term.write_str(&progress).ok();
         }
-    }
 
-    pub fn open_issue(&self, issue: &Issue) -> Option<usize> {
-        //! Makes a POST request to create a new issue with
-        //! the inputted params (title and description).
-        //!
-        //! Panics if the response is not 201 Created or the request fails.
-        //! Returns a number which represents the issue number from GitHub.
-        let mut response = self
-            .client
-            .post(&self.url)
-            .header(AUTHORIZATION, self.auth_header.clone())
-            .json(&issue.to_json())
-            .send()
-            .expect("Failed to create issue");
-
-        if !Self::is_successful_response(response.status()) {
+        let result = client.get(url).headers(headers.clone()).send();
+        if show_progress {
+            term.clear_line().ok();
+        }
+        let mut response = match result {
+            Ok(response) => response,
+            Err(e) => {
+                cli::print_error(&format!("Failed to get issues: {}", e));
+                return None;
+            }
+        };
+
+        if !is_successful_response(response.status()) {
             return None;
         }
 
-        match response.json::<issue::Response>() {
-            Ok(json) => Some(json.get_number()),
-            Err(_) => None,
+        match response.json::<Vec<T>>() {
+            Ok(json_array) => items.extend(json_array),
+            Err(e) => {
+                cli::print_error(&format!("Failed to read issues: {}", e));
+                return None;
+            }
         }
+        next_page = get_next_page(response.headers());
     }
 
-    pub fn get_issues(&self, is_dry_run: bool) -> Option<HashSet<String>> {
-        //! Makes a GET request to retrieve all issues (open and closed)
-        //! with a todo label in the remote repository.
-        //!
-        //! Returns a hashset of the issue titles. Returns early if the response
-        //! is not 200 OK or the request fails.
-        if is_dry_run {
-            return Some(HashSet::new());
+    Some(items)
+}
+
+fn get_next_page(headers: &HeaderMap) -> Option<Url> {
+    //! Parses the Link header of a paginated response and returns the
+    //! url of the next page, or None if this is the last page.
+    //!
+    //! The header is of the form
+    //! <https://api.github.com/...&page=2>; rel="next", <...>; rel="last"
+    let link = headers.get(LINK)?.to_str().ok()?;
+    for part in link.split(',') {
+        let mut sections = part.split(';');
+        let url = sections.next()?.trim();
+        let is_next = sections.any(|param| param.trim() == "rel=\"next\"");
+        if is_next && url.starts_with('<') && url.ends_with('>') {
+            return Url::parse(&url[1..url.len() - 1]).ok();
         }
+    }
 
-        println!(
-            "Fetching all issues with {} label from {}",
-            style(issue::LABEL).cyan(),
-            style(&self.remote_url).italic()
-        );
+    None
+}
 
-        let params = json!({
-            "labels": issue::LABEL,
-            "state": "all",
-        });
-        let mut response = self
-            .client
-            .get(&self.url)
-            .header(AUTHORIZATION, self.auth_header.clone())
-            .query(&params)
-            .send()
-            .expect("Failed to get issues");
-
-        if !Self::is_successful_response(response.status()) {
-            return None;
+fn is_successful_response(status: StatusCode) -> bool {
+    //! Asserts that the status code returned is either
+    //! 200 OK or 201 CREATED.
+    //!
+    //! Otherwise, outputs a detailed description about the error.
+    match status {
+        StatusCode::OK | StatusCode::CREATED => return true,
+        StatusCode::UNAUTHORIZED => cli::print_error(
+            "Unathorized request. \
+             Make sure your access token is valid and \
+             you have pull access to the repository.",
+        ),
+        StatusCode::GONE => {
+            cli::print_error("Issues are disabled in this repository.");
         }
-
-        let mut issues = HashSet::new();
-        if let Ok(json_array) = response.json::<Vec<issue::Response>>() {
-            for result in json_array {
-                issues.insert(result.get_title());
-            }
+        StatusCode::FORBIDDEN => cli::print_error(
+            "You have reached the API rate limit or are not allowed to \
+             access issues. Please try again later.",
+        ),
+        StatusCode::NOT_FOUND => cli::print_error(
+            "Remote repository not found. \
+             If your repository is private check that \
+             your access token has the correct permissions.",
+        ),
+        StatusCode::UNPROCESSABLE_ENTITY => {
+            cli::print_error("Unable to process request.");
         }
+        s => cli::print_error(
+            &format!("Received unexpected status code {}", s).to_string(),
+        ),
+    };
 
-        match issues.len() {
-            0 => println!(
-                "No previously opened issues found in the remote repo."
-            ),
-            n => println!(
-                "Found {} previously opened {} in the remote repo.",
-                style(n).bold(),
-                cli::handle_plural(&n, "issue")
-            ),
-        };
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::thread;
 
-        Some(issues)
+    use reqwest::header::HeaderValue;
+
+    fn link_headers(link: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(LINK, HeaderValue::from_str(link).unwrap());
+        headers
     }
 
-    fn is_successful_response(status: StatusCode) -> bool {
-        //! Asserts that the status code returned is either
-        //! 200 OK or 201 CREATED.
-        //!
-        //! Otherwise, outputs a detailed description about the error.
-        match status {
-            StatusCode::OK | StatusCode::CREATED => return true,
-            StatusCode::UNAUTHORIZED => cli::print_error(
-                "Unathorized request. \
-                 Make sure your access token is valid and \
-                 you have pull access to the repository.",
-            ),
-            StatusCode::GONE => {
-                cli::print_error("Issues are disabled in this repository.");
-            }
-            StatusCode::FORBIDDEN => cli::print_error(
-                "You have reached the GitHub API rate limit. \
-                 Please try again later.",
-            ),
-            StatusCode::NOT_FOUND => cli::print_error(
-                "Remote repository not found. \
-                 If your repository is private check that \
-                 your access token has the correct permissions.",
-            ),
-            StatusCode::UNPROCESSABLE_ENTITY => {
-                cli::print_error("Unable to process request.");
+    #[test]
+    fn next_page_is_parsed_from_github_link_header() {
+        let headers = link_headers(
+            "<https://api.github.com/repositories/1/issues?per_page=100&page=2>; \
+             rel=\"next\", \
+             <https://api.github.com/repositories/1/issues?per_page=100&page=5>; \
+             rel=\"last\"",
+        );
+        let next = get_next_page(&headers).unwrap();
+        assert_eq!(
+            next.as_str(),
+            "https://api.github.com/repositories/1/issues?per_page=100&page=2"
+        );
+    }
+
+    #[test]
+    fn next_page_is_parsed_from_gitlab_link_header() {
+        let headers = link_headers(
+            "<https://gitlab.com/api/v4/projects/g%2Fp/issues?page=1>; \
+             rel=\"prev\", \
+             <https://gitlab.com/api/v4/projects/g%2Fp/issues?page=3>; \
+             rel=\"next\", \
+             <https://gitlab.com/api/v4/projects/g%2Fp/issues?page=1>; \
+             rel=\"first\"",
+        );
+        let next = get_next_page(&headers).unwrap();
+        assert_eq!(
+            next.as_str(),
+            "https://gitlab.com/api/v4/projects/g%2Fp/issues?page=3"
+        );
+    }
+
+    #[test]
+    fn last_page_has_no_next_page() {
+        let headers = link_headers(
+            "<https://api.github.com/repositories/1/issues?page=1>; \
+             rel=\"first\", \
+             <https://api.github.com/repositories/1/issues?page=4>; \
+             rel=\"prev\"",
+        );
+        assert!(get_next_page(&headers).is_none());
+        assert!(get_next_page(&HeaderMap::new()).is_none());
+    }
+
+    #[test]
+    fn every_page_is_fetched() {
+        //! Serves three pages from a local listener, each linking to the
+        //! next one, and checks the items of all of them are returned.
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let server_base = base.clone();
+        let server = thread::spawn(move || {
+            let mut requested = Vec::new();
+            for stream in listener.incoming().take(3) {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                // Skip the headers up to the blank line ending them.
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap() > 2 {
+                    line.clear();
+                }
+
+                let page: usize = request_line
+                    .split("page=")
+                    .nth(1)
+                    .and_then(|rest| rest.split(' ').next())
+                    .map_or(1, |page| page.parse().unwrap());
+                requested.push(page);
+                let link = match page {
+                    3 => String::new(),
+                    _ => format!(
+                        "Link: <{}/issues?page={}>; rel=\"next\"\r\n",
+                        server_base,
+                        page + 1
+                    ),
+                };
+                let body = format!("[{}, {}]", page * 10, page * 10 + 1);
+                write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\
+                     Content-Length: {}\r\n{}Connection: close\r\n\r\n{}",
+                    body.len(),
+                    link,
+                    body
+                )
+                .unwrap();
             }
-            s => cli::print_error(
-                &format!("Received unexpected status code {}", s).to_string(),
-            ),
-        };
+            requested
+        });
+
+        let first_page = Url::parse(&format!("{}/issues", base)).unwrap();
+        let items: Vec<usize> =
+            get_all_pages(&Client::new(), first_page, &HeaderMap::new())
+                .unwrap();
 
-        false
+        assert_eq!(items, vec![10, 11, 20, 21, 30, 31]);
+        assert_eq!(server.join().unwrap(), vec![1, 2, 3]);
     }
 }
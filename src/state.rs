@@ -0,0 +1,179 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use super::cli;
+use super::command;
+use super::issue;
+use super::parse;
+
+use issue::Opened;
+use parse::Todo;
+
+/// Where the state is stored, relative to the git directory.
+const FILE_NAME: &str = "todo-to-issue/state.json";
+
+/// What is remembered between runs, kept in the git directory so it is
+/// never committed.
+#[derive(Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct State {
+    /// Identifies the settings the files were scanned with, since the
+    /// cached todos depend on them (see Args::get_scan_key).
+    scan_key: String,
+    /// Web url of the repository the issues are from.
+    remote_url: String,
+    /// Issues found by the last run that queried the remote repository,
+    /// along with the ones opened since.
+    issues: Vec<Opened>,
+    /// Fingerprints of the todos the user chose to ignore.
+    ignored: BTreeSet<String>,
+    /// Text of the todo comment of each issue, by number, which is searched
+    /// for to find the commit that removed it.
+    comments: BTreeMap<usize, String>,
+    files: BTreeMap<String, Scanned>,
+}
+
+/// The todos found in a file the last time it was scanned.
+#[derive(Deserialize, Serialize)]
+struct Scanned {
+    /// Hash of the contents of the file (see fingerprint::hash).
+    hash: String,
+    is_blamed: bool,
+    todos: Vec<Todo>,
+}
+
+impl State {
+    pub fn load(scan_key: &str) -> State {
+        //! Reads the state left by the previous run, starting afresh if
+        //! there is none or it cannot be parsed.
+        //!
+        //! The cached todos are discarded if the settings they were found
+        //! with have changed.
+        let mut state: State = get_path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default();
+        if state.scan_key != scan_key {
+            state.files.clear();
+            state.scan_key = scan_key.to_string();
+        }
+
+        state
+    }
+
+    pub fn save(&self) {
+        //! Writes the state to the git directory, warning the user if it
+        //! could not be written.
+        let path = match get_path() {
+            Some(path) => path,
+            None => return,
+        };
+        let result =
+            path.parent()
+                .map_or(Ok(()), fs::create_dir_all)
+                .and_then(|_| {
+                    let contents = serde_json::to_string(self)
+                        .map_err(io::Error::other)?;
+                    fs::write(&path, contents)
+                });
+        if let Err(error) = result {
+            cli::print_warning(&format!(
+                "Unable to save the state to {}: {}",
+                path.display(),
+                error
+            ));
+        }
+    }
+
+    pub fn get_issues(&self, remote_url: &str) -> Vec<Opened> {
+        //! Returns the issues of the repository cached by the last run that
+        //! queried it, if any.
+        match self.remote_url == remote_url {
+            true => self.issues.clone(),
+            false => Vec::new(),
+        }
+    }
+
+    pub fn set_issues(&mut self, remote_url: &str, issues: &[Opened]) {
+        self.remote_url = remote_url.to_string();
+        self.issues = issues.to_vec();
+    }
+
+    pub fn add_issue(&mut self, issue: Opened) {
+        self.issues.push(issue);
+    }
+
+    pub fn set_issue_state(&mut self, number: usize, is_open: bool) {
+        for issue in &mut self.issues {
+            if issue.number == number {
+                issue.is_open = is_open;
+            }
+        }
+    }
+
+    pub fn get_comment(&self, number: usize) -> Option<&str> {
+        self.comments.get(&number).map(|text| text.as_str())
+    }
+
+    pub fn set_comment(&mut self, number: usize, text: &str) {
+        self.comments.insert(number, text.to_string());
+    }
+
+    pub fn is_ignored(&self, fingerprint: &str) -> bool {
+        self.ignored.contains(fingerprint)
+    }
+
+    pub fn ignore(&mut self, fingerprint: &str) {
+        self.ignored.insert(fingerprint.to_string());
+    }
+
+    pub fn get_todos(
+        &self,
+        file: &str,
+        hash: &str,
+        needs_blame: bool,
+    ) -> Option<Vec<Todo>> {
+        //! Returns the todos found the last time the file was scanned if it
+        //! has not changed since.
+        //!
+        //! Files are scanned again if their todos need to be blamed and
+        //! some of them were not, e.g. because they were not committed yet.
+        let scanned = self.files.get(file)?;
+        let is_blamed = scanned.is_blamed
+            && scanned.todos.iter().all(|todo| todo.blame.is_some());
+        if scanned.hash != hash || (needs_blame && !is_blamed) {
+            return None;
+        }
+
+        Some(scanned.todos.clone())
+    }
+
+    pub fn set_todos(
+        &mut self,
+        file: &str,
+        hash: String,
+        is_blamed: bool,
+        todos: &[Todo],
+    ) {
+        let scanned = Scanned {
+            hash,
+            is_blamed,
+            todos: todos.to_vec(),
+        };
+        self.files.insert(file.to_string(), scanned);
+    }
+
+    pub fn retain_files(&mut self, files: &[String]) {
+        //! Forgets the files that no longer exist.
+        let files: BTreeSet<&str> = files.iter().map(|f| f.as_str()).collect();
+        self.files.retain(|file, _| files.contains(file.as_str()));
+    }
+}
+
+fn get_path() -> Option<PathBuf> {
+    command::get_git_path(FILE_NAME)
+}
@@ -0,0 +1,534 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+struct Language {
+    extensions: &'static [&'static str],
+    filenames: &'static [&'static str],
+    line: &'static [&'static str],
+    block: &'static [(&'static str, &'static str)],
+    quotes: &'static [char],
+    /// Whether single quotes denote character literals such as 'a' or
+    /// '\n' but are also used on their own, e.g. for lifetimes in Rust.
+    char_literals: bool,
+}
+
+/// Language hints of code blocks in markdown for files whose extension
+/// or name is not recognized as one.
+const HINTS: &[(&str, &str)] = &[
+    ("h", "c"),
+    ("hh", "cpp"),
+    ("hpp", "cpp"),
+    ("hxx", "cpp"),
+    ("mk", "makefile"),
+    ("Makefile", "makefile"),
+    ("GNUmakefile", "makefile"),
+    ("Dockerfile", "dockerfile"),
+    ("Jenkinsfile", "groovy"),
+    ("CMakeLists.txt", "cmake"),
+    ("Gemfile", "ruby"),
+    ("Rakefile", "ruby"),
+];
+
+const C_BLOCK: &[(&str, &str)] = &[("/*", "*/")];
+const NO_BLOCK: &[(&str, &str)] = &[];
+
+const LANGUAGES: &[Language] = &[
+    Language {
+        extensions: &[
+            "c", "h", "cc", "cpp", "cxx", "hh", "hpp", "hxx", "cs", "java",
+            "js", "jsx", "mjs", "ts", "tsx", "go", "swift", "kt", "kts",
+            "scala", "dart", "groovy", "gradle", "m", "mm", "proto", "scss",
+            "less", "sol", "v", "zig",
+        ],
+        filenames: &["Jenkinsfile"],
+        line: &["//"],
+        block: C_BLOCK,
+        quotes: &['"', '\''],
+        char_literals: false,
+    },
+    Language {
+        // Single quotes are left out since they also denote lifetimes, so
+        // char literals like '"' are skipped separately.
+        extensions: &["rs"],
+        filenames: &[],
+        line: &["//"],
+        block: C_BLOCK,
+        quotes: &['"'],
+        char_literals: true,
+    },
+    Language {
+        extensions: &["css"],
+        filenames: &[],
+        line: &[],
+        block: C_BLOCK,
+        quotes: &['"', '\''],
+        char_literals: false,
+    },
+    Language {
+        extensions: &["php"],
+        filenames: &[],
+        line: &["//", "#"],
+        block: C_BLOCK,
+        quotes: &['"', '\''],
+        char_literals: false,
+    },
+    Language {
+        extensions: &[
+            "sh",
+            "bash",
+            "zsh",
+            "fish",
+            "py",
+            "pyi",
+            "rb",
+            "pl",
+            "pm",
+            "r",
+            "yaml",
+            "yml",
+            "toml",
+            "cmake",
+            "mk",
+            "tf",
+            "coffee",
+            "cfg",
+            "conf",
+            "properties",
+            "dockerfile",
+            "gitignore",
+            "ex",
+            "exs",
+        ],
+        filenames: &[
+            "Makefile",
+            "makefile",
+            "GNUmakefile",
+            "Dockerfile",
+            "Containerfile",
+            "CMakeLists.txt",
+            "Gemfile",
+            "Rakefile",
+            "Vagrantfile",
+            "Procfile",
+            "BUILD",
+            "WORKSPACE",
+        ],
+        line: &["#"],
+        block: NO_BLOCK,
+        quotes: &['"', '\''],
+        char_literals: false,
+    },
+    Language {
+        extensions: &["nix"],
+        filenames: &[],
+        line: &["#"],
+        block: C_BLOCK,
+        quotes: &['"'],
+        char_literals: false,
+    },
+    Language {
+        extensions: &["sql"],
+        filenames: &[],
+        line: &["--"],
+        block: C_BLOCK,
+        quotes: &['\'', '"'],
+        char_literals: false,
+    },
+    Language {
+        extensions: &["lua"],
+        filenames: &[],
+        line: &["--"],
+        block: &[("--[[", "]]")],
+        quotes: &['"', '\''],
+        char_literals: false,
+    },
+    Language {
+        extensions: &["hs", "lhs", "elm", "purs"],
+        filenames: &[],
+        line: &["--"],
+        block: &[("{-", "-}")],
+        quotes: &['"'],
+        char_literals: false,
+    },
+    Language {
+        extensions: &["ada", "adb", "ads", "vhd", "vhdl"],
+        filenames: &[],
+        line: &["--"],
+        block: NO_BLOCK,
+        quotes: &['"'],
+        char_literals: false,
+    },
+    Language {
+        extensions: &[
+            "lisp", "lsp", "cl", "el", "clj", "cljs", "cljc", "edn", "scm",
+            "ss", "rkt", "asm", "s",
+        ],
+        filenames: &[],
+        line: &[";"],
+        block: NO_BLOCK,
+        quotes: &['"'],
+        char_literals: false,
+    },
+    Language {
+        extensions: &["ini"],
+        filenames: &[],
+        line: &[";", "#"],
+        block: NO_BLOCK,
+        quotes: &['"'],
+        char_literals: false,
+    },
+    Language {
+        extensions: &["erl", "hrl", "tex", "sty", "cls"],
+        filenames: &[],
+        line: &["%"],
+        block: NO_BLOCK,
+        quotes: &['"'],
+        char_literals: false,
+    },
+    Language {
+        extensions: &["vb", "vbs", "bas", "frm"],
+        filenames: &[],
+        line: &["'"],
+        block: NO_BLOCK,
+        quotes: &['"'],
+        char_literals: false,
+    },
+    Language {
+        extensions: &["html", "htm", "xhtml", "xml", "svg", "xsl", "md"],
+        filenames: &[],
+        line: &[],
+        block: &[("<!--", "-->")],
+        quotes: &[],
+        char_literals: false,
+    },
+];
+
+/// Comment delimiters used for files that are not in the registry.
+const FALLBACK_LINE: &[&str] = &["//", "#"];
+
+#[derive(Clone)]
+pub struct Syntax {
+    line: Vec<String>,
+    block: Vec<(String, String)>,
+    quotes: Vec<char>,
+    char_literals: bool,
+}
+
+/// A comment found on a single line of a file.
+pub struct Comment<'a> {
+    /// Byte offset of the comment delimiter in the line, or 0 if the line
+    /// continues a block comment opened on a previous line.
+    pub column: usize,
+    /// Byte offset of the comment text in the line.
+    pub offset: usize,
+    pub text: &'a str,
+    pub is_block: bool,
+}
+
+pub struct Registry {
+    extensions: HashMap<String, Syntax>,
+    filenames: HashMap<String, Syntax>,
+    fallback: Syntax,
+}
+
+impl Syntax {
+    fn from_language(language: &Language) -> Syntax {
+        Syntax {
+            line: language.line.iter().map(|s| s.to_string()).collect(),
+            block: language
+                .block
+                .iter()
+                .map(|(start, end)| (start.to_string(), end.to_string()))
+                .collect(),
+            quotes: language.quotes.to_vec(),
+            char_literals: language.char_literals,
+        }
+    }
+
+    pub fn from_delimiters(delimiters: &[String]) -> Result<Syntax, String> {
+        //! Creates a syntax from a list of comment delimiters.
+        //!
+        //! Each delimiter is either a line comment token such as "//" or a
+        //! block comment given as a start and end token separated by
+        //! whitespace such as "/* */". Strings are assumed to use
+        //! double quotes.
+        let mut syntax = Syntax {
+            line: Vec::new(),
+            block: Vec::new(),
+            quotes: vec!['"'],
+            char_literals: false,
+        };
+        for delimiter in delimiters {
+            let tokens: Vec<&str> = delimiter.split_whitespace().collect();
+            match tokens.len() {
+                1 => syntax.line.push(tokens[0].to_string()),
+                2 => syntax
+                    .block
+                    .push((tokens[0].to_string(), tokens[1].to_string())),
+                _ => {
+                    return Err(format!(
+                        "Invalid comment delimiter \"{}\"",
+                        delimiter
+                    ))
+                }
+            }
+        }
+        if syntax.line.is_empty() && syntax.block.is_empty() {
+            return Err("No comment delimiters given".to_string());
+        }
+
+        Ok(syntax)
+    }
+
+    pub fn find_comments<'a>(
+        &self,
+        line: &'a str,
+        open_block: &mut Option<usize>,
+    ) -> Vec<Comment<'a>> {
+        //! Returns every comment on a line, skipping over delimiters that
+        //! appear inside string literals.
+        //!
+        //! `open_block` carries the index of a block comment that has not been
+        //! closed yet between consecutive calls, so it should start as None
+        //! and be reused for every line of the same file.
+        let mut comments = Vec::new();
+        let mut index = 0;
+
+        if let Some(block) = *open_block {
+            let end = &self.block[block].1;
+            match line.find(end.as_str()) {
+                Some(end_index) => {
+                    comments.push(Comment {
+                        column: 0,
+                        offset: 0,
+                        text: &line[..end_index],
+                        is_block: true,
+                    });
+                    *open_block = None;
+                    index = end_index + end.len();
+                }
+                None => {
+                    comments.push(Comment {
+                        column: 0,
+                        offset: 0,
+                        text: line,
+                        is_block: true,
+                    });
+                    return comments;
+                }
+            }
+        }
+
+        let mut quote = None;
+        while let Some(c) = line[index..].chars().next() {
+            let rest = &line[index..];
+            if let Some(q) = quote {
+                if c == '\\' {
+                    index += c.len_utf8();
+                    if let Some(escaped) = line[index..].chars().next() {
+                        index += escaped.len_utf8();
+                    }
+                    continue;
+                }
+                if c == q {
+                    quote = None;
+                }
+                index += c.len_utf8();
+                continue;
+            }
+
+            let block = self
+                .block
+                .iter()
+                .enumerate()
+                .find(|(_, (start, _))| rest.starts_with(start.as_str()));
+            if let Some((block_index, (start, end))) = block {
+                let offset = index + start.len();
+                match line[offset..].find(end.as_str()) {
+                    Some(end_index) => {
+                        comments.push(Comment {
+                            column: index,
+                            offset,
+                            text: &line[offset..offset + end_index],
+                            is_block: true,
+                        });
+                        index = offset + end_index + end.len();
+                        continue;
+                    }
+                    None => {
+                        comments.push(Comment {
+                            column: index,
+                            offset,
+                            text: &line[offset..],
+                            is_block: true,
+                        });
+                        *open_block = Some(block_index);
+                        return comments;
+                    }
+                }
+            }
+
+            let token = self.line.iter().find(|t| rest.starts_with(t.as_str()));
+            if let Some(token) = token {
+                let offset = index + token.len();
+                comments.push(Comment {
+                    column: index,
+                    offset,
+                    text: &line[offset..],
+                    is_block: false,
+                });
+                return comments;
+            }
+
+            if c == '\'' && self.char_literals {
+                if let Some(length) = get_char_literal_length(rest) {
+                    index += length;
+                    continue;
+                }
+            }
+            if self.quotes.contains(&c) {
+                quote = Some(c);
+            }
+            index += c.len_utf8();
+        }
+
+        comments
+    }
+}
+
+fn get_char_literal_length(text: &str) -> Option<usize> {
+    //! Returns the length in bytes of the character literal the text starts
+    //! with, e.g. '"' or '\'', or None if the quote does not start one.
+    let mut chars = text.char_indices().skip(1);
+    let (_, first) = chars.next()?;
+    if first == '\\' {
+        // Escapes may span several characters, e.g. '\u{1F600}'.
+        chars.next()?;
+        return chars
+            .take_while(|(_, c)| *c != ' ')
+            .find(|(_, c)| *c == '\'')
+            .map(|(index, _)| index + 1);
+    }
+
+    match chars.next()? {
+        (index, '\'') => Some(index + 1),
+        _ => None,
+    }
+}
+
+impl Registry {
+    pub fn new() -> Registry {
+        //! Creates a registry containing the comment syntax of every
+        //! built-in language.
+        let mut extensions = HashMap::new();
+        let mut filenames = HashMap::new();
+        for language in LANGUAGES {
+            let syntax = Syntax::from_language(language);
+            for extension in language.extensions {
+                extensions.insert(extension.to_string(), syntax.clone());
+            }
+            for filename in language.filenames {
+                filenames.insert(filename.to_string(), syntax.clone());
+            }
+        }
+
+        let fallback = Syntax {
+            line: FALLBACK_LINE.iter().map(|s| s.to_string()).collect(),
+            block: vec![("/*".to_string(), "*/".to_string())],
+            quotes: vec!['"'],
+            char_literals: false,
+        };
+
+        Registry {
+            extensions,
+            filenames,
+            fallback,
+        }
+    }
+
+    pub fn insert(&mut self, key: &str, syntax: Syntax) {
+        //! Adds a custom mapping that overrides any built-in one.
+        //!
+        //! The key is matched against both the file name (e.g. "Justfile")
+        //! and the extension (e.g. "jsonnet", with or without a leading dot).
+        let key = key.trim_start_matches('.');
+        self.filenames.insert(key.to_string(), syntax.clone());
+        self.extensions.insert(key.to_lowercase(), syntax);
+    }
+
+    pub fn get(&self, path: &str) -> &Syntax {
+        //! Returns the comment syntax for a file based on its name,
+        //! falling back to C and Bash style comments for unknown files.
+        let path = Path::new(path);
+        let filename = path.file_name().and_then(|name| name.to_str());
+        if let Some(syntax) = filename.and_then(|f| self.filenames.get(f)) {
+            return syntax;
+        }
+
+        path.extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(|extension| {
+                self.extensions.get(&extension.to_lowercase())
+            })
+            .unwrap_or(&self.fallback)
+    }
+}
+
+pub fn get_language_hint(path: &str) -> String {
+    //! Returns the language of a file to highlight its code blocks in
+    //! markdown, which is its extension unless it has a special name,
+    //! e.g. "rs" for main.rs and "makefile" for Makefile.
+    let path = Path::new(path);
+    let filename = path.file_name().and_then(|name| name.to_str());
+    let extension = path.extension().and_then(|ext| ext.to_str());
+    for key in filename.iter().chain(extension.iter()) {
+        if let Some((_, hint)) = HINTS.iter().find(|(name, _)| name == key) {
+            return hint.to_string();
+        }
+    }
+
+    extension.unwrap_or("").to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_texts<'a>(syntax: &Syntax, line: &'a str) -> Vec<&'a str> {
+        let mut open_block = None;
+        syntax
+            .find_comments(line, &mut open_block)
+            .iter()
+            .map(|comment| comment.text)
+            .collect()
+    }
+
+    #[test]
+    fn rust_char_literals_do_not_open_strings() {
+        let registry = Registry::new();
+        let syntax = registry.get("src/main.rs");
+        let texts = get_texts(syntax, "c == '\"' // TODO handle quotes");
+        assert_eq!(texts, vec![" TODO handle quotes"]);
+        let texts = get_texts(syntax, "['\\'', '\\u{22}', '\"'] // TODO");
+        assert_eq!(texts, vec![" TODO"]);
+    }
+
+    #[test]
+    fn rust_lifetimes_are_not_char_literals() {
+        let registry = Registry::new();
+        let syntax = registry.get("src/main.rs");
+        let texts = get_texts(syntax, "fn f<'a>(s: &'a str) {} // TODO");
+        assert_eq!(texts, vec![" TODO"]);
+        let texts = get_texts(syntax, "let s: &'static str = \"//\"; // TODO");
+        assert_eq!(texts, vec![" TODO"]);
+    }
+
+    #[test]
+    fn delimiters_in_strings_are_skipped() {
+        let registry = Registry::new();
+        let texts = get_texts(registry.get("a.py"), "x = '#' # TODO");
+        assert_eq!(texts, vec![" TODO"]);
+        let texts = get_texts(registry.get("a.c"), "s = \"/*\"; /* a */ b");
+        assert_eq!(texts, vec![" a "]);
+    }
+}
@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+
+const OPEN: &str = "{{";
+const CLOSE: &str = "}}";
+
+/// Names of the placeholders that can be used in a template.
+pub const VARIABLES: &[&str] = &[
+    "title",
+    "marker",
+    "label",
+    "file",
+    "line",
+    "column",
+    "description",
+    "snippet",
+    "permalink",
+    "author",
+    "email",
+    "commit",
+    "date",
+];
+
+/// The title and body templates of issues, either of which may be unset.
+#[derive(Default)]
+pub struct Templates {
+    pub title: Option<Template>,
+    pub body: Option<Template>,
+}
+
+enum Part {
+    Text(String),
+    Variable(String),
+}
+
+/// A title or body of an issue with {{placeholders}} that are filled in
+/// for each todo, e.g. "{{marker}} in {{file}}: {{title}}".
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+impl Template {
+    pub fn parse(source: &str, name: &str) -> Result<Template, String> {
+        //! Splits the template into text and placeholders, which may have
+        //! whitespace around their name e.g. {{ title }}.
+        //!
+        //! Returns an error message mentioning the name of the template if a
+        //! placeholder is not closed or is not one of the known variables.
+        let mut parts = Vec::new();
+        let mut rest = source;
+        while let Some(start) = rest.find(OPEN) {
+            if start > 0 {
+                parts.push(Part::Text(rest[..start].to_string()));
+            }
+            let after_open = &rest[start + OPEN.len()..];
+            let end = match after_open.find(CLOSE) {
+                Some(end) => end,
+                None => {
+                    return Err(format!(
+                        "Unclosed placeholder \"{}\" in the {}.",
+                        rest[start..].lines().next().unwrap_or(""),
+                        name
+                    ))
+                }
+            };
+
+            let variable = after_open[..end].trim();
+            if !VARIABLES.contains(&variable) {
+                return Err(format!(
+                    "Unknown placeholder \"{{{{{}}}}}\" in the {}. \
+                     Expected one of {}.",
+                    variable,
+                    name,
+                    VARIABLES.join(", ")
+                ));
+            }
+            parts.push(Part::Variable(variable.to_string()));
+            rest = &after_open[end + CLOSE.len()..];
+        }
+        if !rest.is_empty() {
+            parts.push(Part::Text(rest.to_string()));
+        }
+
+        Ok(Template { parts })
+    }
+
+    pub fn render(&self, values: &HashMap<&str, String>) -> String {
+        //! Fills in every placeholder with its value, leaving it empty if
+        //! the value is unknown e.g. the author of an uncommitted todo.
+        self.parts
+            .iter()
+            .map(|part| match part {
+                Part::Text(text) => text.as_str(),
+                Part::Variable(name) => {
+                    values.get(name.as_str()).map_or("", |v| v.as_str())
+                }
+            })
+            .collect()
+    }
+}
+
+impl Templates {
+    pub fn parse(
+        title: Option<&str>,
+        body: Option<&str>,
+        keyword: Option<&str>,
+    ) -> Result<Templates, String> {
+        //! Parses the title and body templates, if they are set, of the
+        //! issues of every marker or the one with the given keyword.
+        let parse = |source: Option<&str>, part: &str| {
+            let name = match keyword {
+                Some(keyword) => format!("{} template of {}", part, keyword),
+                None => format!("{} template", part),
+            };
+            source
+                .map(|source| Template::parse(source, &name))
+                .transpose()
+        };
+
+        Ok(Templates {
+            title: parse(title, "title")?,
+            body: parse(body, "body")?,
+        })
+    }
+}
@@ -0,0 +1,183 @@
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use console::style;
+
+use super::cli;
+use super::github;
+use super::gitlab;
+use super::issue;
+use super::remote;
+
+use cli::Args;
+use github::GitHub;
+use gitlab::GitLab;
+use issue::{Issue, Opened};
+use remote::Remote;
+
+pub const KINDS: &[&str] = &["github", "gitlab"];
+
+/// The services issues can be opened in.
+#[derive(Clone, Copy)]
+pub enum Kind {
+    GitHub,
+    GitLab,
+}
+
+impl Kind {
+    pub fn from_host(host: &str) -> Kind {
+        //! Guesses the service from the host of the remote, defaulting
+        //! to GitHub unless the host mentions GitLab e.g. gitlab.com or
+        //! gitlab.example.com.
+        match host.to_ascii_lowercase().contains("gitlab") {
+            true => Kind::GitLab,
+            false => Kind::GitHub,
+        }
+    }
+
+    pub fn get_name(self) -> &'static str {
+        match self {
+            Kind::GitHub => "GitHub",
+            Kind::GitLab => "GitLab",
+        }
+    }
+
+    pub fn get_permalink(
+        self,
+        remote: &Remote,
+        commit: &str,
+        path: &str,
+        line_number: u32,
+    ) -> String {
+        //! Returns the url of a line of a file at a commit, which keeps
+        //! pointing to the same line as the file changes.
+        let blob = match self {
+            Kind::GitHub => "blob",
+            Kind::GitLab => "-/blob",
+        };
+        let path: String = path
+            .chars()
+            .map(|c| match c {
+                ' ' | '#' | '?' | '%' => format!("%{:02X}", c as u8),
+                c => c.to_string(),
+            })
+            .collect();
+
+        format!(
+            "{}/{}/{}/{}#L{}",
+            remote.get_web_url(),
+            blob,
+            commit,
+            path,
+            line_number
+        )
+    }
+
+    pub fn get_commit_url(self, remote: &Remote, commit: &str) -> String {
+        let path = match self {
+            Kind::GitHub => "commit",
+            Kind::GitLab => "-/commit",
+        };
+
+        format!("{}/{}/{}", remote.get_web_url(), path, commit)
+    }
+
+    pub fn get_token_variable(self) -> &'static str {
+        //! Returns the environment variable the token is read from
+        //! if it is not passed.
+        match self {
+            Kind::GitHub => "GITHUB_TOKEN",
+            Kind::GitLab => "GITLAB_TOKEN",
+        }
+    }
+}
+
+impl FromStr for Kind {
+    type Err = String;
+
+    fn from_str(string: &str) -> Result<Kind, String> {
+        match string {
+            "github" => Ok(Kind::GitHub),
+            "gitlab" => Ok(Kind::GitLab),
+            _ => Err(format!(
+                "Invalid tracker \"{}\". Expected one of {}.",
+                string,
+                KINDS.join(", ")
+            )),
+        }
+    }
+}
+
+/// An issue tracker of a remote repository.
+pub trait Tracker {
+    /// Opens a new issue and returns its number, or None if the
+    /// request failed.
+    fn open_issue(&self, issue: &Issue) -> Option<usize>;
+
+    /// Adds a comment to an issue, returning whether it succeeded.
+    fn comment_on_issue(&self, number: usize, comment: &str) -> bool;
+
+    /// Closes or reopens an issue, returning whether it succeeded.
+    fn set_issue_state(&self, number: usize, is_open: bool) -> bool;
+
+    /// Returns every issue (open and closed) with the label, or None if
+    /// a request failed.
+    fn get_issues_with_label(&self, label: &str) -> Option<Vec<Opened>>;
+
+    /// Returns the url of the repository's web page.
+    fn get_web_url(&self) -> String;
+
+    fn get_issues(&self, labels: &[String]) -> Option<Vec<Opened>> {
+        //! Retrieves all issues (open and closed) with any of the given
+        //! labels in the remote repository.
+        //!
+        //! Returns the issues ordered by number, leaving out duplicates of
+        //! issues with several of the labels. Returns early if a request
+        //! fails.
+        let styled_labels: Vec<String> = labels
+            .iter()
+            .map(|label| style(label).cyan().to_string())
+            .collect();
+        cli::print_info(&format!(
+            "Fetching all issues with {} {} from {}",
+            styled_labels.join(", "),
+            cli::handle_plural(&labels.len(), "label"),
+            style(self.get_web_url()).italic()
+        ));
+
+        let mut number_to_issue = BTreeMap::new();
+        for label in labels {
+            for issue in self.get_issues_with_label(label)? {
+                number_to_issue.insert(issue.number, issue);
+            }
+        }
+        let issues: Vec<Opened> = number_to_issue.into_values().collect();
+
+        match issues.len() {
+            0 => cli::print_info(
+                "No previously opened issues found in the remote repo.",
+            ),
+            n => cli::print_info(&format!(
+                "Found {} previously opened {} in the remote repo.",
+                style(n).bold(),
+                cli::handle_plural(&n, "issue")
+            )),
+        };
+
+        Some(issues)
+    }
+}
+
+pub fn new(args: &Args) -> Box<dyn Tracker> {
+    //! Creates the tracker of the remote repository, which is left
+    //! blank if no remote was found.
+    let remote = args.get_remote().cloned().unwrap_or_default();
+    match args.get_tracker() {
+        Kind::GitHub => {
+            Box::new(GitHub::new(args.get_token(), &remote, args.get_api_url()))
+        }
+        Kind::GitLab => {
+            Box::new(GitLab::new(args.get_token(), &remote, args.get_api_url()))
+        }
+    }
+}
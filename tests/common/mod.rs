@@ -0,0 +1,85 @@
+//! A generated repository to run the tool in.
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+use serde_json::Value;
+
+pub struct Repo {
+    root: PathBuf,
+}
+
+impl Repo {
+    pub fn new(name: &str) -> Repo {
+        //! Creates an empty repository with a GitHub remote.
+        let root = env::temp_dir().join(format!(
+            "todo-to-issue-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+
+        let repo = Repo { root };
+        repo.git(&["init", "-q", "-b", "main"]);
+        repo.git(&["remote", "add", "origin", "https://github.com/o/r.git"]);
+        repo
+    }
+
+    pub fn write(&self, path: &str, contents: &str) {
+        let path = self.root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    pub fn commit(&self, message: &str) {
+        //! Commits every file in the working tree.
+        self.git(&["add", "."]);
+        self.git(&[
+            "-c",
+            "user.name=a",
+            "-c",
+            "user.email=a@b",
+            "commit",
+            "-q",
+            "-m",
+            message,
+        ]);
+    }
+
+    pub fn git(&self, args: &[&str]) {
+        let status = Command::new("git")
+            .args(args)
+            .current_dir(&self.root)
+            .status()
+            .expect("Failed to execute git");
+        assert!(status.success(), "git {} failed", args.join(" "));
+    }
+
+    pub fn find_todos(&self, directory: &str, args: &[&str]) -> Vec<Value> {
+        //! Returns the todos found when running from the directory with the
+        //! arguments.
+        let output = Command::new(env!("CARGO_BIN_EXE_todo-to-issue"))
+            .args(["--format", "json"])
+            .args(args)
+            .current_dir(self.root.join(directory))
+            .env_remove("GITHUB_TOKEN")
+            .output()
+            .expect("Failed to run todo-to-issue");
+        assert!(
+            output.status.success(),
+            "todo-to-issue failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+
+        serde_json::from_slice(&output.stdout).unwrap()
+    }
+}
+
+impl Drop for Repo {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
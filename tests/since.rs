@@ -0,0 +1,45 @@
+//! Searches only the todos added since a base branch, with uncommitted and
+//! untracked changes in the working tree.
+
+mod common;
+
+use common::Repo;
+
+fn get_titles(repo: &Repo, args: &[&str]) -> Vec<(String, u64)> {
+    repo.find_todos("", args)
+        .iter()
+        .map(|todo| {
+            let title = todo["title"].as_str().unwrap().to_string();
+            (title, todo["line"].as_u64().unwrap())
+        })
+        .collect()
+}
+
+#[test]
+fn uncommitted_lines_shift_the_added_lines() {
+    let repo = Repo::new("since");
+    repo.write("src/old.rs", "// TODO: old\n");
+    repo.commit("Add old");
+    repo.git(&["checkout", "-q", "-b", "feature"]);
+    repo.write("src/e.rs", "// TODO: new in e\nlet e = 1;\n");
+    repo.commit("Add e");
+
+    // Uncommitted lines above the new todo.
+    repo.write("src/e.rs", "let a = 1;\nlet b = 2;\n\n// TODO: new in e\n");
+    let expected = vec![("new in e".to_string(), 4)];
+    assert_eq!(get_titles(&repo, &["--since", "main"]), expected);
+
+    // Untracked files are only searched in the working tree.
+    repo.write("src/u.rs", "// TODO: untracked\n");
+    assert_eq!(get_titles(&repo, &["--since", "main"]), expected);
+    let titles =
+        get_titles(&repo, &["--since", "main", "--source", "worktree"]);
+    assert_eq!(
+        titles,
+        vec![("new in e".to_string(), 4), ("untracked".to_string(), 1)]
+    );
+
+    // A revision is compared with the base as committed.
+    let titles = get_titles(&repo, &["--since", "main", "--rev", "feature"]);
+    assert_eq!(titles, vec![("new in e".to_string(), 1)]);
+}
@@ -0,0 +1,42 @@
+//! Runs the tool from a subdirectory of a generated repository, which must
+//! give the same results as running it from the root.
+
+mod common;
+
+use common::Repo;
+
+fn create_repo(name: &str) -> Repo {
+    //! Creates a repository with a committed todo in two directories.
+    let repo = Repo::new(name);
+    repo.write("src/a.rs", "// TODO: Fix a\nlet a = 1;\n");
+    repo.write("other/o.sh", "# TODO: Fix o\n");
+    repo.commit("Add todos");
+    repo
+}
+
+#[test]
+fn files_are_relative_to_the_root() {
+    let repo = create_repo("files");
+    for directory in &["", "other", "src"] {
+        let todos = repo.find_todos(directory, &[]);
+        let files: Vec<&str> =
+            todos.iter().map(|t| t["file"].as_str().unwrap()).collect();
+        assert_eq!(files, vec!["other/o.sh", "src/a.rs"], "in {}", directory);
+    }
+}
+
+#[test]
+fn fingerprints_and_bodies_do_not_depend_on_the_directory() {
+    let repo = create_repo("fingerprints");
+    let expected = repo.find_todos("", &[]);
+    for directory in &["other", "src"] {
+        let todos = repo.find_todos(directory, &[]);
+        for (todo, expected) in todos.iter().zip(&expected) {
+            assert_eq!(todo["fingerprint"], expected["fingerprint"]);
+            assert_eq!(todo["body"], expected["body"]);
+        }
+    }
+    let body = expected[1]["body"].as_str().unwrap();
+    assert!(body.contains("/blob/"), "no permalink in {}", body);
+    assert!(body.contains("src/a.rs#L1"), "wrong path in {}", body);
+}